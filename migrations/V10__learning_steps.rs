@@ -0,0 +1,18 @@
+use barrel::backend::Sqlite;
+use barrel::{types, Migration};
+
+pub fn migration() -> String {
+    let mut m = Migration::new();
+
+    // items that already exist have had plenty of time to get going, so
+    // they start out with no learning steps left.
+    m.change_table("items", |t| {
+        t.add_column("learning_steps", types::integer().default(0));
+    });
+
+    m.change_table("trash", |t| {
+        t.add_column("learning_steps", types::integer().default(0));
+    });
+
+    m.make::<Sqlite>()
+}
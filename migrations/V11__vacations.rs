@@ -0,0 +1,18 @@
+use barrel::backend::Sqlite;
+use barrel::{types, Migration};
+
+pub fn migration() -> String {
+    let mut m = Migration::new();
+
+    m.create_table("vacations", |t| {
+        t.add_column("id", types::primary());
+
+        // a vacation without a tag applies to every item
+        t.add_column("tag_id", types::integer().nullable(true));
+
+        t.add_column("starts", types::datetime());
+        t.add_column("ends", types::datetime());
+    });
+
+    m.make::<Sqlite>()
+}
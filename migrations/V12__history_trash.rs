@@ -0,0 +1,29 @@
+use barrel::backend::Sqlite;
+use barrel::{types, Migration};
+
+pub fn migration() -> String {
+    let mut m = Migration::new();
+
+    // item IDs get reused once an item is deleted, so history for a
+    // trashed item points at its row in the trash instead. Otherwise the
+    // next item to get that ID would inherit it.
+    m.change_table("history", |t| {
+        t.add_column("trash_id", types::integer().nullable(true));
+    });
+
+    // IDs only get reused after a deletion, so anything finished before
+    // (or on the day) an item with that ID went in the trash belongs to the
+    // trashed copy. If there's more than one, it's the earliest one deleted
+    // after the finish.
+    m.inject_custom(
+        "UPDATE history SET trash_id = (SELECT MIN(trash.id) FROM trash WHERE trash.item_id = history.item_id AND trash.deleted_at >= history.finished_at)",
+    );
+
+    // and anything left that doesn't belong to an item was for something
+    // that's been emptied out of the trash since.
+    m.inject_custom(
+        "DELETE FROM history WHERE trash_id IS NULL AND item_id NOT IN (SELECT id FROM items)",
+    );
+
+    m.make::<Sqlite>()
+}
@@ -0,0 +1,18 @@
+use barrel::backend::Sqlite;
+use barrel::{types, Migration};
+
+pub fn migration() -> String {
+    let mut m = Migration::new();
+
+    // SQLite can only add one column per ALTER TABLE, so these have to be
+    // separate changes.
+    m.change_table("items", |t| {
+        t.add_column("archived", types::boolean().default(false));
+    });
+
+    m.change_table("items", |t| {
+        t.add_column("paused_until", types::datetime().nullable(true));
+    });
+
+    m.make::<Sqlite>()
+}
@@ -0,0 +1,29 @@
+use barrel::backend::Sqlite;
+use barrel::{types, Migration};
+
+pub fn migration() -> String {
+    let mut m = Migration::new();
+
+    m.create_table("trash", |t| {
+        t.add_column("id", types::primary());
+        t.add_column("item_id", types::integer());
+        t.add_column("text", types::text());
+        t.add_column("tag", types::text().nullable(true));
+
+        // scheduling
+        t.add_column("cadence", types::integer());
+        t.add_column("next", types::datetime());
+
+        // PID
+        t.add_column("integral", types::float());
+        t.add_column("last_error", types::float());
+
+        // retirement
+        t.add_column("archived", types::boolean());
+        t.add_column("paused_until", types::datetime().nullable(true));
+
+        t.add_column("deleted_at", types::datetime());
+    });
+
+    m.make::<Sqlite>()
+}
@@ -0,0 +1,21 @@
+use barrel::backend::Sqlite;
+use barrel::{types, Migration};
+
+pub fn migration() -> String {
+    let mut m = Migration::new();
+
+    m.create_table("history", |t| {
+        t.add_column("id", types::primary());
+        t.add_column("item_id", types::integer());
+        t.add_column("bump", types::text());
+
+        // when the item was due, and when it actually got finished
+        t.add_column("due", types::datetime());
+        t.add_column("finished_at", types::datetime());
+
+        // the cadence after taking the bump into account
+        t.add_column("cadence", types::integer());
+    });
+
+    m.make::<Sqlite>()
+}
@@ -0,0 +1,213 @@
+//! Changes to items that can be made from outside the CLI (`tempo serve` and
+//! `tempo rpc`.) These take the same inputs as the matching commands, but
+//! return typed errors so that callers can tell the client what went wrong
+//! in a structured way.
+
+use crate::cadence::Cadence;
+use crate::cli::parse_utc_datetime;
+use crate::date::Date;
+use crate::history::Finish;
+use crate::item::{Bump, FinishError, Item, DEFAULT_LEARNING_STEPS};
+use crate::tag::Tag;
+use crate::trash::Trashed;
+use clap::ArgEnum;
+use core::str::FromStr;
+use rusqlite::Connection;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("couldn't find item with ID {0}")]
+    NotFound(u64),
+    #[error("{0}")]
+    InvalidParams(String),
+    #[error("there's already an item with this text (\"{}\", ID {})", .0.text, .0.id)]
+    Duplicate(Box<Item>),
+    #[error(transparent)]
+    Finish(#[from] FinishError),
+    #[error("{0}")]
+    Conflict(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddParams {
+    pub text: String,
+    pub tag: Option<String>,
+    pub cadence: Option<String>,
+    pub next: Option<String>,
+    pub learning_steps: Option<u32>,
+    #[serde(default)]
+    pub allow_duplicate: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EditParams {
+    pub text: Option<String>,
+    pub tag: Option<String>,
+    pub next: Option<String>,
+    pub cadence: Option<String>,
+    pub bump: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FinishParams {
+    pub bump: String,
+}
+
+impl FinishParams {
+    /// The bump these params name. `finish` takes a `Bump`, so the server
+    /// and RPC handlers call this to turn what the client sent into one.
+    pub fn bump(&self) -> Result<Bump, Error> {
+        parse_bump(&self.bump)
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnoozeParams {
+    pub until: String,
+}
+
+impl Error {
+    /// How this error looks in JSON: `{"error": "<message>"}`, plus the
+    /// existing item for duplicates. `tempo add --format json` uses this
+    /// too, so a duplicate looks the same no matter where it came from.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({ "error": format!("{self:#}") });
+
+        if let Error::Duplicate(existing) = self {
+            body["existing"] = serde_json::to_value(existing).unwrap_or_default();
+        }
+
+        body
+    }
+}
+
+pub fn get(conn: &Connection, id: u64) -> Result<Item, Error> {
+    Item::get(id, conn).map_err(|err| {
+        if matches!(
+            err.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::QueryReturnedNoRows)
+        ) {
+            Error::NotFound(id)
+        } else {
+            Error::Internal(err)
+        }
+    })
+}
+
+pub fn add(conn: &Connection, params: &AddParams) -> Result<Item, Error> {
+    if !params.allow_duplicate {
+        if let Some(existing) = Item::find_duplicate(&params.text, conn)? {
+            return Err(Error::Duplicate(Box::new(existing)));
+        }
+    }
+
+    let (item, _) = Item::create(
+        &params.text,
+        params.tag.as_deref(),
+        params.cadence.as_deref().map(parse_cadence).transpose()?,
+        params.next.as_deref().map(parse_date).transpose()?,
+        params.learning_steps.unwrap_or(DEFAULT_LEARNING_STEPS),
+        conn,
+    )?;
+
+    Ok(item)
+}
+
+/// Apply the same changes as `tempo edit`, with the same restrictions on
+/// which can go together.
+pub fn edit(conn: &Connection, id: u64, params: &EditParams) -> Result<Item, Error> {
+    let scheduling = [&params.next, &params.cadence, &params.bump]
+        .iter()
+        .filter(|change| change.is_some())
+        .count();
+    if scheduling > 1 {
+        return Err(Error::InvalidParams(
+            "only one of next, cadence, and bump can be changed at a time".into(),
+        ));
+    }
+
+    let mut item = get(conn, id)?;
+
+    if let Some(text) = &params.text {
+        item.text.clone_from(text);
+    }
+
+    if let Some(tag) = &params.tag {
+        item.tag_id = Some(Tag::get_or_create_by_name(conn, tag)?.id);
+    }
+
+    if let Some(next) = &params.next {
+        item.next = parse_date(next)?;
+    }
+
+    if let Some(cadence) = &params.cadence {
+        item.cadence = parse_cadence(cadence)?;
+    }
+
+    if let Some(bump) = &params.bump {
+        let adjustment = item.bump_cadence(&parse_bump(bump)?);
+        item.next = item.next + adjustment;
+    }
+
+    item.save(conn)?;
+
+    Ok(item)
+}
+
+pub fn finish(conn: &Connection, id: u64, bump: &Bump) -> Result<Item, Error> {
+    let mut item = get(conn, id)?;
+    let due = item.next;
+    item.finish(bump)?;
+    item.save(conn)?;
+    Finish::record(conn, &item, bump, due)?;
+
+    Ok(item)
+}
+
+/// Hide an item from "ready" until a date, like `tempo pause`.
+pub fn snooze(conn: &Connection, id: u64, params: &SnoozeParams) -> Result<Item, Error> {
+    let until = parse_date(&params.until)?;
+
+    let mut item = get(conn, id)?;
+    if item.archived {
+        return Err(Error::Conflict(format!(
+            "item with ID {id} is archived. Unarchive it before snoozing it"
+        )));
+    }
+
+    item.paused_until = Some(until);
+    item.save(conn)?;
+
+    Ok(item)
+}
+
+/// Move an item to the trash, returning it as it was just before.
+pub fn delete(conn: &Connection, id: u64) -> Result<Item, Error> {
+    let item = get(conn, id)?;
+    Trashed::trash(&item, conn)?;
+
+    Ok(item)
+}
+
+fn parse_cadence(raw: &str) -> Result<Cadence, Error> {
+    Cadence::from_str(raw)
+        .map_err(|err| Error::InvalidParams(format!("couldn't parse cadence \"{raw}\": {err}")))
+}
+
+fn parse_date(raw: &str) -> Result<Date, Error> {
+    parse_utc_datetime(raw)
+        .map_err(|err| Error::InvalidParams(format!("couldn't parse date \"{raw}\": {err}")))
+}
+
+fn parse_bump(raw: &str) -> Result<Bump, Error> {
+    Bump::from_str(raw, true).map_err(|_| {
+        Error::InvalidParams(format!("couldn't parse bump \"{raw}\". Try one of much-earlier, earlier, just-right, later, or much-later"))
+    })
+}
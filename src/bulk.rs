@@ -0,0 +1,356 @@
+use crate::cadence::Cadence;
+use crate::cli::parse_utc_datetime;
+use crate::date::Date;
+use crate::item::Item;
+use anyhow::{bail, Result};
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+use rusqlite::Connection;
+
+/// One item to add, parsed out of a bulk import file.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Row {
+    pub line: u64,
+    pub text: String,
+    pub tag: Option<String>,
+    pub cadence: Option<Cadence>,
+    pub next: Option<Date>,
+}
+
+/// Something wrong with a line in a bulk import file. We collect these
+/// instead of stopping at the first one so that you can fix everything in
+/// one go.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+impl Display for RowError {
+    fn fmt(&self, out: &mut Formatter<'_>) -> fmt::Result {
+        write!(out, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CsvRow {
+    #[serde(default)]
+    text: String,
+    tag: Option<String>,
+    cadence: Option<String>,
+    next: Option<String>,
+    url: Option<String>,
+}
+
+/// Parse CSV with a header row. Recognized columns are `text`, `tag`,
+/// `cadence`, `next`, and `url` (in any order.) Everything but `text` is
+/// optional, and if there's a URL it's added to the end of the text.
+pub fn parse_csv(input: &str) -> Vec<Result<Row, RowError>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(input.as_bytes());
+
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(err) => {
+            return vec![Err(RowError {
+                line: 1,
+                message: format!("couldn't read the header row: {err}"),
+            })]
+        }
+    };
+
+    reader
+        .records()
+        .map(|result| {
+            let record = result.map_err(|err| RowError {
+                line: err.position().map_or(0, csv::Position::line),
+                message: err.to_string(),
+            })?;
+
+            let line = record.position().map_or(0, csv::Position::line);
+
+            let raw: CsvRow = record.deserialize(Some(&headers)).map_err(|err| RowError {
+                line,
+                message: err.to_string(),
+            })?;
+
+            csv_row(line, raw)
+        })
+        .collect()
+}
+
+fn csv_row(line: u64, raw: CsvRow) -> Result<Row, RowError> {
+    let text = match (non_empty(Some(raw.text)), non_empty(raw.url)) {
+        (Some(text), Some(url)) => format!("{text} {url}"),
+        (Some(text), None) => text,
+        (None, Some(url)) => url,
+        (None, None) => {
+            return Err(RowError {
+                line,
+                message: "a row needs text or a URL".into(),
+            })
+        }
+    };
+
+    let cadence = match non_empty(raw.cadence) {
+        Some(cadence) => Some(Cadence::from_str(&cadence).map_err(|err| RowError {
+            line,
+            message: format!("couldn't parse cadence \"{cadence}\": {err}"),
+        })?),
+        None => None,
+    };
+
+    let next = match non_empty(raw.next) {
+        Some(next) => Some(parse_utc_datetime(&next).map_err(|err| RowError {
+            line,
+            message: format!("couldn't parse next \"{next}\": {err}"),
+        })?),
+        None => None,
+    };
+
+    Ok(Row {
+        line,
+        text,
+        tag: non_empty(raw.tag),
+        cadence,
+        next,
+    })
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+/// Parse a Markdown document. Every list item becomes an item, and the
+/// closest heading above it becomes its tag.
+pub fn parse_markdown(input: &str) -> Vec<Result<Row, RowError>> {
+    let mut tag: Option<String> = None;
+    let mut rows = Vec::new();
+
+    for (line, raw) in (1..).zip(input.lines()) {
+        let trimmed = raw.trim();
+
+        if trimmed.starts_with('#') {
+            tag = non_empty(Some(trimmed.trim_start_matches('#').trim().to_string()));
+            continue;
+        }
+
+        let bullet = ['-', '*', '+'].iter().find_map(|marker| {
+            trimmed
+                .strip_prefix(*marker)
+                .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+        });
+
+        if let Some(content) = bullet {
+            // task list items get imported too, but the checkbox isn't part
+            // of the text.
+            let content = content.trim();
+            let text = ["[ ] ", "[x] ", "[X] "]
+                .iter()
+                .find_map(|checkbox| content.strip_prefix(checkbox))
+                .unwrap_or(content)
+                .trim();
+
+            rows.push(if text.is_empty() {
+                Err(RowError {
+                    line,
+                    message: "this list item is empty".into(),
+                })
+            } else {
+                Ok(Row {
+                    line,
+                    text: text.to_string(),
+                    tag: tag.clone(),
+                    cadence: None,
+                    next: None,
+                })
+            });
+        }
+    }
+
+    rows
+}
+
+impl Row {
+    /// Add this row to the store, using the same defaults as `tempo add`.
+    pub fn insert(
+        &self,
+        conn: &Connection,
+        allow_duplicate: bool,
+        learning_steps: u32,
+    ) -> Result<Item> {
+        if !allow_duplicate {
+            if let Some(existing) = Item::find_duplicate(&self.text, conn)? {
+                bail!(
+                    "\"{}\" is a duplicate of item ID {}",
+                    existing.text,
+                    existing.id
+                )
+            }
+        }
+
+        let (item, _) = Item::create(
+            &self.text,
+            self.tag.as_deref(),
+            self.cadence,
+            self.next,
+            learning_steps,
+            conn,
+        )?;
+
+        Ok(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod csv {
+        use super::*;
+
+        #[test]
+        fn parses_all_columns() {
+            let rows = parse_csv(
+                "text,tag,cadence,next,url\nRead this,bookmarks,6w,2022-03-01,https://example.com\n",
+            );
+
+            assert_eq!(
+                vec![Ok(Row {
+                    line: 2,
+                    text: "Read this https://example.com".into(),
+                    tag: Some("bookmarks".into()),
+                    cadence: Some(Cadence::weeks(6)),
+                    next: Some(parse_utc_datetime("2022-03-01").unwrap()),
+                })],
+                rows
+            );
+        }
+
+        #[test]
+        fn columns_are_optional_and_unordered() {
+            let rows = parse_csv("url,text\nhttps://example.com,\n,Just text\n");
+
+            assert_eq!(
+                vec!["https://example.com".to_string(), "Just text".to_string()],
+                rows.into_iter()
+                    .map(|row| row.unwrap().text)
+                    .collect::<Vec<String>>()
+            );
+        }
+
+        #[test]
+        fn reports_errors_with_line_numbers() {
+            let rows = parse_csv("text,cadence\nfine,1d\nbad,soon\n,\n");
+
+            assert!(rows[0].is_ok());
+            assert_eq!(3, rows[1].as_ref().unwrap_err().line);
+            assert_eq!(4, rows[2].as_ref().unwrap_err().line);
+        }
+    }
+
+    mod markdown {
+        use super::*;
+
+        #[test]
+        fn headings_become_tags() {
+            let rows = parse_markdown(
+                "- untagged\n\n# Journaling\n\nSome intro text.\n\n- What went well today?\n* [ ] Who do I miss?\n\n## Bookmarks\n+ https://example.com\n",
+            );
+
+            assert_eq!(
+                vec![
+                    (1, "untagged".to_string(), None),
+                    (
+                        7,
+                        "What went well today?".to_string(),
+                        Some("Journaling".to_string())
+                    ),
+                    (
+                        8,
+                        "Who do I miss?".to_string(),
+                        Some("Journaling".to_string())
+                    ),
+                    (
+                        11,
+                        "https://example.com".to_string(),
+                        Some("Bookmarks".to_string())
+                    ),
+                ],
+                rows.into_iter()
+                    .map(|row| {
+                        let row = row.unwrap();
+                        (row.line, row.text, row.tag)
+                    })
+                    .collect::<Vec<(u64, String, Option<String>)>>()
+            );
+        }
+
+        #[test]
+        fn empty_list_items_are_errors() {
+            let rows = parse_markdown("- fine\n- \n");
+
+            assert!(rows[0].is_ok());
+            assert_eq!(2, rows[1].as_ref().unwrap_err().line);
+        }
+    }
+
+    mod insert {
+        use super::*;
+        use crate::item::DEFAULT_LEARNING_STEPS;
+
+        fn conn() -> Connection {
+            let mut conn =
+                Connection::open_in_memory().expect("couldn't open an in-memory database");
+            crate::db::migrations::runner()
+                .run(&mut conn)
+                .expect("couldn't migrate database");
+
+            conn
+        }
+
+        fn row() -> Row {
+            Row {
+                line: 1,
+                text: "Text".into(),
+                tag: None,
+                cadence: None,
+                next: None,
+            }
+        }
+
+        #[test]
+        fn uses_add_defaults() {
+            let conn = conn();
+
+            let item = row().insert(&conn, false, DEFAULT_LEARNING_STEPS).unwrap();
+
+            assert_eq!(Cadence::days(1), item.cadence);
+            assert_eq!(Date::today() + Cadence::days(1), item.next);
+        }
+
+        #[test]
+        fn cadence_is_calculated_based_on_next() {
+            let conn = conn();
+            let mut row = row();
+            row.next = Some(Date::today() + Cadence::weeks(2));
+
+            assert_eq!(
+                Cadence::weeks(2),
+                row.insert(&conn, false, DEFAULT_LEARNING_STEPS)
+                    .unwrap()
+                    .cadence
+            );
+        }
+
+        #[test]
+        fn refuses_duplicates() {
+            let conn = conn();
+            row().insert(&conn, false, DEFAULT_LEARNING_STEPS).unwrap();
+
+            assert!(row().insert(&conn, false, DEFAULT_LEARNING_STEPS).is_err());
+            assert!(row().insert(&conn, true, DEFAULT_LEARNING_STEPS).is_ok());
+        }
+    }
+}
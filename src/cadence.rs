@@ -15,7 +15,7 @@ static WEEKS: i64 = DAYS * 7;
 static MONTHS: i64 = DAYS * 30;
 static YEARS: i64 = DAYS * 365;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, serde::Serialize, PartialOrd)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, serde::Serialize, serde::Deserialize, PartialOrd)]
 pub struct Cadence {
     pub days: i64,
 }
@@ -36,6 +36,22 @@ impl Cadence {
     pub fn years(years: i64) -> Cadence {
         Self::days(years * YEARS)
     }
+
+    /// The median of some cadences (rounding down), or `None` if there
+    /// aren't any.
+    pub fn median(cadences: impl IntoIterator<Item = Cadence>) -> Option<Cadence> {
+        let mut sorted: Vec<i64> = cadences.into_iter().map(|cadence| cadence.days).collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_unstable();
+
+        // these are the same item when there's an odd number of cadences
+        let lower = sorted[(sorted.len() - 1) / 2];
+        let upper = sorted[sorted.len() / 2];
+
+        Some(Self::days(lower + (upper - lower) / 2))
+    }
 }
 
 impl Default for Cadence {
@@ -309,4 +325,34 @@ mod tests {
             assert_eq!("-2d", Cadence::days(-2).to_string());
         }
     }
+
+    mod median {
+        use super::*;
+
+        #[test]
+        fn empty() {
+            assert_eq!(None, Cadence::median([]));
+        }
+
+        #[test]
+        fn odd_count() {
+            assert_eq!(
+                Some(Cadence::days(4)),
+                Cadence::median([Cadence::days(9), Cadence::days(1), Cadence::days(4)])
+            );
+        }
+
+        #[test]
+        fn even_count_averages() {
+            assert_eq!(
+                Some(Cadence::days(5)),
+                Cadence::median([
+                    Cadence::days(8),
+                    Cadence::days(2),
+                    Cadence::days(6),
+                    Cadence::days(4),
+                ])
+            );
+        }
+    }
 }
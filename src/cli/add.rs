@@ -1,11 +1,11 @@
+use crate::api;
 use crate::cadence::Cadence;
 use crate::date::Date;
 use crate::format::Format;
-use crate::item::Item;
-use crate::tag::Tag;
-use anyhow::{Context, Result};
+use crate::item::{Item, DEFAULT_LEARNING_STEPS};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
-use rusqlite::{params, Connection};
+use rusqlite::Connection;
 
 #[derive(Parser, Debug)]
 pub struct Command {
@@ -26,41 +26,67 @@ pub struct Command {
     /// When should this next be scheduled?
     #[clap(short, long, parse(try_from_str = super::parse_utc_datetime))]
     next: Option<Date>,
+
+    /// Add the item even if there's already one with the same text
+    #[clap(long)]
+    allow_duplicate: bool,
+
+    /// How many times to double or halve the cadence based on feedback
+    /// before fine-tuning it. Use 0 if you're confident in the cadence
+    /// already.
+    #[clap(long, env = "TEMPO_LEARNING_STEPS", default_value_t = DEFAULT_LEARNING_STEPS)]
+    learning_steps: u32,
 }
 
 impl Command {
     pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
-        let today = Date::today();
-
-        let tag_id: Option<u64> = match &self.tag {
-            Some(tag_name) => Some(Tag::get_or_create_by_name(conn, tag_name)?.id),
-            None => None,
-        };
-
-        let id: u64 = conn
-            .query_row(
-                // This *could* be a RETURNING for all the columns instead of
-                // just ID, but making more queries in SQLite is super fast and
-                // it lets us use the shared "get an item" infrastructure here,
-                // which is better overall.
-                "INSERT INTO items (text, cadence, next, tag_id) VALUES (?, ?, ?, ?) RETURNING id",
-                params![
-                    self.text.join(" "),
-                    self.get_cadence(today),
-                    self.get_next(today),
-                    tag_id,
-                ],
-                |row| row.get(0),
-            )
-            .context("could not insert the new row into the database")?;
-
-        let item = Item::get(id, conn)?;
+        let text = self.text.join(" ");
+
+        if !self.allow_duplicate {
+            if let Some(existing) = Item::find_duplicate(&text, conn)? {
+                if format == Format::Json {
+                    println!(
+                        "{}",
+                        api::Error::Duplicate(Box::new(existing.clone())).to_json()
+                    );
+                }
+
+                bail!(
+                    "there's already an item with this text (\"{}\", ID {}.) Use `tempo edit {}` to change it, or pass --allow-duplicate to add it anyway",
+                    existing.text,
+                    existing.id,
+                    existing.id,
+                )
+            }
+        }
+
+        let (item, suggestion) = Item::create(
+            &text,
+            self.tag.as_deref(),
+            self.cadence,
+            self.next,
+            self.learning_steps,
+            conn,
+        )?;
 
         match format {
-            Format::Human => println!(
-                "Added \"{}\" with ID {}. Currently scheduled on {} and every {} thereafter",
-                item.text, item.id, item.next, item.cadence,
-            ),
+            Format::Human => {
+                println!(
+                    "Added \"{}\" with ID {}. Currently scheduled on {} and every {} thereafter",
+                    item.text, item.id, item.next, item.cadence,
+                );
+
+                match (&suggestion, &self.tag) {
+                    (Some(suggestion), Some(tag)) => println!(
+                        "That's the median cadence of the {} settled items tagged \"{}\". Pass --cadence to choose your own",
+                        suggestion.settled, tag
+                    ),
+                    _ if self.cadence.is_none() && self.next.is_none() => println!(
+                        "That's the default cadence. Pass --cadence to choose your own"
+                    ),
+                    _ => (),
+                }
+            }
             Format::Json => println!(
                 "{}",
                 serde_json::to_string(&item).context("could not convert this item to JSON")?
@@ -69,18 +95,6 @@ impl Command {
 
         Ok(())
     }
-
-    fn get_cadence(&self, today: Date) -> Cadence {
-        match (self.cadence, self.next) {
-            (Some(cadence), _) => cadence,
-            (None, Some(_)) => (self.get_next(today) - today).into(),
-            (None, None) => Cadence::days(1),
-        }
-    }
-
-    fn get_next(&self, today: Date) -> Date {
-        self.next.unwrap_or_else(|| today + self.get_cadence(today))
-    }
 }
 
 #[cfg(test)]
@@ -93,6 +107,8 @@ mod test {
             tag: None,
             cadence: None,
             next: None,
+            allow_duplicate: false,
+            learning_steps: DEFAULT_LEARNING_STEPS,
         }
     }
 
@@ -105,59 +121,6 @@ mod test {
         conn
     }
 
-    #[test]
-    fn next_is_used() {
-        let next = Date::today() + Cadence::weeks(1);
-
-        let mut command = default();
-        command.next = Some(next);
-
-        assert_eq!(next, command.get_next(Date::today()));
-    }
-
-    #[test]
-    fn next_is_calculated_based_on_cadence() {
-        let today = Date::today();
-        let cadence = Cadence::days(1);
-
-        let mut command = default();
-        command.cadence = Some(cadence);
-        command.next = None; // just to be explicit
-
-        assert_eq!(today + cadence, command.get_next(today));
-    }
-
-    #[test]
-    fn cadence_is_used() {
-        let cadence = Cadence::weeks(1);
-
-        let mut command = default();
-        command.cadence = Some(cadence);
-
-        assert_eq!(cadence, command.get_cadence(Date::today()));
-    }
-
-    #[test]
-    fn cadence_is_calculated_based_on_next() {
-        let today = Date::today();
-        let next = today + Cadence::weeks(1);
-
-        let mut command = default();
-        command.cadence = None; // just to be explicit
-        command.next = Some(next);
-
-        assert_eq!(Cadence::from(next - today), command.get_cadence(today));
-    }
-
-    #[test]
-    fn cadence_is_one_day_if_neither_is_present() {
-        let mut command = default();
-        command.cadence = None; // just to be explicit
-        command.next = None; // just to be explicit
-
-        assert_eq!(Cadence::days(1), command.get_cadence(Date::today()));
-    }
-
     #[test]
     fn adds_specified_text() {
         let mut command = default();
@@ -222,6 +185,48 @@ mod test {
         );
     }
 
+    #[test]
+    fn refuses_duplicates() {
+        let command = default();
+        let conn = conn();
+
+        command
+            .run(&conn, Format::Human)
+            .expect("command should not fail");
+
+        let mut duplicate = default();
+        duplicate.text = vec!["  text ".into()];
+
+        assert!(duplicate.run(&conn, Format::Human).is_err());
+        assert_eq!(
+            1,
+            conn.query_row("SELECT COUNT(*) FROM items", [], |row| row.get::<_, u64>(0))
+                .expect("failed to query the database")
+        );
+    }
+
+    #[test]
+    fn allows_duplicates_when_asked() {
+        let command = default();
+        let conn = conn();
+
+        command
+            .run(&conn, Format::Human)
+            .expect("command should not fail");
+
+        let mut duplicate = default();
+        duplicate.allow_duplicate = true;
+
+        duplicate
+            .run(&conn, Format::Human)
+            .expect("command should not fail");
+        assert_eq!(
+            2,
+            conn.query_row("SELECT COUNT(*) FROM items", [], |row| row.get::<_, u64>(0))
+                .expect("failed to query the database")
+        );
+    }
+
     #[test]
     fn adds_specified_next() {
         let mut command = default();
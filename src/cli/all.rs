@@ -1,5 +1,6 @@
 use crate::format::Format;
 use crate::item::Item;
+use crate::status::WithStatus;
 use crate::tag::Tag;
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -10,29 +11,20 @@ pub struct Command {
     /// Tag to filter by
     #[clap(long)]
     tag: Option<String>,
+
+    /// Include archived items, which are otherwise hidden
+    #[clap(long)]
+    include_archived: bool,
 }
 
 impl Command {
     pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
-        let pulled = Item::all(conn).context("could not pull items")?;
-
-        let tag_id = match &self.tag {
-            Some(tag_name) => Some(
-                Tag::get_by_name(conn, tag_name)
-                    .context("could not get tag with that name")?
-                    .id,
-            ),
-            None => None,
-        };
-
-        let filtered: Vec<Item> = pulled
-            .filter(|item| tag_id.map_or(true, |id| item.id == id))
-            .collect();
+        let filtered = query(conn, self.tag.as_deref(), self.include_archived)?;
 
         match format {
             Format::Human => {
-                for item in filtered {
-                    println!("{}: {} (due {})", item.id, item.text, item.next);
+                for WithStatus { item, status } in filtered {
+                    println!("{}: {} (due {}, {})", item.id, item.text, item.next, status);
                 }
             }
             Format::Json => println!(
@@ -44,3 +36,87 @@ impl Command {
         Ok(())
     }
 }
+
+/// Get every item with its status, optionally limited to one tag. This is
+/// shared with `tempo serve` and `tempo rpc`, so that they always agree.
+pub fn query(
+    conn: &Connection,
+    tag: Option<&str>,
+    include_archived: bool,
+) -> Result<Vec<WithStatus>> {
+    let pulled = Item::all(conn).context("could not pull items")?;
+
+    let tag_id = match tag {
+        Some(tag_name) => Some(
+            Tag::get_by_name(conn, tag_name)
+                .context("could not get tag with that name")?
+                .id,
+        ),
+        None => None,
+    };
+
+    pulled
+        .filter(|item| tag_id.is_none_or(|id| item.tag_id == Some(id)))
+        .filter(|item| include_archived || !item.archived)
+        .map(|item| WithStatus::new(item, conn))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cadence::Cadence;
+    use crate::date::Date;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn
+    }
+
+    #[test]
+    fn filters_by_tag() {
+        let conn = conn();
+        // the tag's ID matches the untagged item's ID, so we can tell if
+        // we're comparing the wrong thing.
+        conn.execute("INSERT INTO tags (id, name) VALUES (1, \"tag\")", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO items (id, text, cadence, next) VALUES (1, ?, ?, ?)",
+            params!["untagged", Cadence::days(1), Date::today()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO items (id, text, cadence, next, tag_id) VALUES (2, ?, ?, ?, 1)",
+            params!["tagged", Cadence::days(1), Date::today()],
+        )
+        .unwrap();
+
+        let items = query(&conn, Some("tag"), false).unwrap();
+
+        assert_eq!(
+            vec!["tagged"],
+            items
+                .iter()
+                .map(|with_status| with_status.item.text.as_str())
+                .collect::<Vec<&str>>()
+        );
+    }
+
+    #[test]
+    fn hides_archived_items() {
+        let conn = conn();
+        conn.execute(
+            "INSERT INTO items (text, cadence, next, archived) VALUES (?, ?, ?, ?)",
+            params!["archived", Cadence::days(1), Date::today(), true],
+        )
+        .unwrap();
+
+        assert_eq!(0, query(&conn, None, false).unwrap().len());
+        assert_eq!(1, query(&conn, None, true).unwrap().len());
+    }
+}
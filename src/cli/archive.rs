@@ -0,0 +1,93 @@
+use crate::format::Format;
+use crate::item::Item;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// ID of the item to archive
+    id: u64,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        let mut item = Item::get(self.id, conn)
+            .with_context(|| format!("couldn't load item with ID {}", self.id))?;
+
+        if item.archived {
+            bail!("item with ID {} is already archived", self.id)
+        }
+
+        item.archived = true;
+
+        item.save(conn)
+            .with_context(|| format!("couldn't save item with ID {}", self.id))?;
+
+        match format {
+            Format::Human => println!(
+                "Archived item with ID {}. It won't show up again until you unarchive it",
+                self.id
+            ),
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&item).context("couldn't convert item to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cadence::Cadence;
+    use crate::date::Date;
+    use rusqlite::params;
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::runner().run(&mut conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params!["test", Cadence::days(1), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn archives_item() {
+        let conn = setup();
+        let command = Command::try_parse_from(&["archive", "1"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        assert!(Item::get(1, &conn).unwrap().archived);
+    }
+
+    #[test]
+    fn keeps_scheduling_state() {
+        let conn = setup();
+        let before = Item::get(1, &conn).unwrap();
+
+        let command = Command::try_parse_from(&["archive", "1"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        let after = Item::get(1, &conn).unwrap();
+        assert_eq!(before.cadence, after.cadence);
+        assert_eq!(before.next, after.next);
+        assert_eq!(before.pid, after.pid);
+    }
+
+    #[test]
+    fn fails_if_already_archived() {
+        let conn = setup();
+        let command = Command::try_parse_from(&["archive", "1"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        assert!(command.run(&conn, Format::Human).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use crate::format::Format;
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use clap::Parser;
+use rusqlite::{Connection, DatabaseName};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// Where to write the backup. If absent, we'll write a timestamped file
+    /// into a "backups" directory next to the database and clean up old
+    /// backups there.
+    path: Option<PathBuf>,
+
+    /// How many backups to keep in the default backup directory, counting
+    /// the one we just made (so at least 1.) Has no effect if you specify a
+    /// path explicitly.
+    #[clap(long, default_value = "10", parse(try_from_str = parse_keep))]
+    keep: usize,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Output {
+    path: PathBuf,
+    removed: Vec<PathBuf>,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, db_path: &Path, format: Format) -> Result<()> {
+        let (path, rotate_in) = if let Some(explicit) = &self.path {
+            (explicit.clone(), None)
+        } else {
+            let dir = db_path
+                .parent()
+                .context("couldn't get the directory the database is in")?
+                .join("backups");
+
+            std::fs::create_dir_all(&dir).with_context(|| {
+                format!("could not create the backup directory {}", dir.display())
+            })?;
+
+            let name = format!("tempo-{}.sqlite3", Local::now().format("%Y%m%dT%H%M%S"));
+            let path = dir.join(name);
+
+            // names only go down to the second, so a second backup in the
+            // same second would otherwise replace the first one.
+            if path.exists() {
+                bail!(
+                    "there's already a backup at {}. Wait a second and try again",
+                    path.display()
+                )
+            }
+
+            (path, Some(dir))
+        };
+
+        // SQLite's online backup API copies a consistent snapshot even if
+        // another process writes to the database while we're working.
+        conn.backup(DatabaseName::Main, &path, None)
+            .with_context(|| format!("could not back up the database to {}", path.display()))?;
+
+        let removed = match rotate_in {
+            Some(dir) => rotate(&dir, self.keep)?,
+            None => Vec::new(),
+        };
+
+        match format {
+            Format::Human => {
+                println!("Backed up the database to {}", path.display());
+
+                if !removed.is_empty() {
+                    println!("Removed {} old backup(s)", removed.len());
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&Output { path, removed })
+                    .context("could not convert backup info to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_keep(raw: &str) -> Result<usize> {
+    let keep: usize = raw.parse().context("couldn't parse a number of backups")?;
+    if keep == 0 {
+        bail!("we need to keep at least the backup we just made, so this has to be 1 or more")
+    }
+
+    Ok(keep)
+}
+
+/// Remove all but the newest `keep` backups in `dir`. Backup names start
+/// with a timestamp, so sorting them by name sorts them by age too.
+fn rotate(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("could not read the backup directory {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("tempo-") && name.ends_with(".sqlite3"))
+        })
+        .collect();
+
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(keep);
+    let removed: Vec<PathBuf> = backups.drain(..excess).collect();
+
+    for path in &removed {
+        log::info!("removing old backup {}", path.display());
+        std::fs::remove_file(path)
+            .with_context(|| format!("could not remove old backup {}", path.display()))?;
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("tempo-test-{}-backup-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn.execute(
+            "INSERT INTO items (text, next) VALUES (\"test\", \"2022-01-01T00:00:00+00:00\")",
+            [],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn writes_snapshot_to_explicit_path() {
+        let dir = scratch_dir("explicit");
+        let path = dir.join("snapshot.sqlite3");
+
+        let command = Command::try_parse_from(["backup", path.to_str().unwrap()]).unwrap();
+        command
+            .run(&conn(), &dir.join("tempo.sqlite3"), Format::Human)
+            .unwrap();
+
+        let copy = Connection::open(&path).unwrap();
+        assert_eq!(
+            "test".to_string(),
+            copy.query_row("SELECT text FROM items", [], |row| row.get::<_, String>(0))
+                .unwrap()
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn writes_to_backups_dir_by_default() {
+        let dir = scratch_dir("default");
+
+        let command = Command::try_parse_from(["backup"]).unwrap();
+        command
+            .run(&conn(), &dir.join("tempo.sqlite3"), Format::Human)
+            .unwrap();
+
+        assert_eq!(1, std::fs::read_dir(dir.join("backups")).unwrap().count());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_to_keep_nothing() {
+        assert!(Command::try_parse_from(["backup", "--keep", "0"]).is_err());
+        assert!(Command::try_parse_from(["backup", "--keep", "1"]).is_ok());
+    }
+
+    #[test]
+    fn refuses_to_overwrite_a_backup() {
+        let dir = scratch_dir("overwrite");
+        let backups = dir.join("backups");
+        std::fs::create_dir_all(&backups).unwrap();
+
+        let command = Command::try_parse_from(["backup"]).unwrap();
+        let db_path = dir.join("tempo.sqlite3");
+
+        // there's a tiny chance the clock ticks over between the two, so
+        // try a few times before deciding the check doesn't work
+        let refused = (0..3).any(|_| {
+            command.run(&conn(), &db_path, Format::Human).is_ok()
+                && command.run(&conn(), &db_path, Format::Human).is_err()
+        });
+        assert!(refused);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rotate_keeps_newest() {
+        let dir = scratch_dir("rotate");
+        for name in [
+            "tempo-20220101T000000.sqlite3",
+            "tempo-20220102T000000.sqlite3",
+            "tempo-20220103T000000.sqlite3",
+            "unrelated.txt",
+        ] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let removed = rotate(&dir, 2).unwrap();
+
+        assert_eq!(vec![dir.join("tempo-20220101T000000.sqlite3")], removed);
+        assert!(dir.join("tempo-20220103T000000.sqlite3").exists());
+        assert!(dir.join("unrelated.txt").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
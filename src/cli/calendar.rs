@@ -0,0 +1,266 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::forecast::{self, Occurrence};
+use crate::format::Format;
+use crate::item::Item;
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate};
+use clap::Parser;
+use rusqlite::Connection;
+use std::fmt::Write;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// Which month to show, like 2022-03. Defaults to this month.
+    #[clap(parse(try_from_str = parse_month))]
+    month: Option<NaiveDate>,
+
+    /// Only show items with these tags
+    #[clap(long, short)]
+    tag: Vec<String>,
+
+    /// Also show later repetitions in the month, assuming each one gets
+    /// finished on time with "just-right" feedback
+    #[clap(long, short)]
+    repeat: bool,
+
+    /// List the items due each day below the calendar
+    #[clap(long, short)]
+    list: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Day {
+    #[serde(serialize_with = "serialize_naive")]
+    date: NaiveDate,
+    count: usize,
+    today: bool,
+    overdue: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<Vec<Item>>,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        // the default month and the today marker both come from here, so
+        // they can't disagree about what day it is.
+        let today = Date::today().local();
+        let first = self.month.unwrap_or_else(|| today.with_day(1).unwrap());
+        let after = next_month(first);
+
+        // occurrences are stored as UTC dates, so look a little past the
+        // end of the month and let `Date::local` sort out which day each
+        // one actually lands on.
+        let until = Date::ymd(after.year(), after.month(), after.day()) + Cadence::days(2);
+        let occurrences = forecast::occurrences(conn, &self.tag, until, self.repeat)?;
+
+        let days = self.days(first, today, occurrences);
+
+        match format {
+            Format::Human => {
+                print!("{}", grid(first, &days));
+                if self.list {
+                    print!("{}", list(&days));
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&days).context("couldn't convert calendar to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+
+    fn days(&self, first: NaiveDate, today: NaiveDate, occurrences: Vec<Occurrence>) -> Vec<Day> {
+        let mut days: Vec<Day> = first
+            .iter_days()
+            .take_while(|date| date.month() == first.month())
+            .map(|date| Day {
+                date,
+                count: 0,
+                today: date == today,
+                overdue: false,
+                items: if self.list { Some(Vec::new()) } else { None },
+            })
+            .collect();
+
+        for occurrence in occurrences {
+            let date = occurrence.date.local();
+            if let Some(day) = days.iter_mut().find(|day| day.date == date) {
+                day.count += 1;
+                day.overdue |= date < today;
+                if let Some(items) = &mut day.items {
+                    items.push(occurrence.item);
+                }
+            }
+        }
+
+        days
+    }
+}
+
+fn parse_month(raw: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{raw}-01"), "%Y-%m-%d")
+        .context("couldn't parse a month. Try something like 2022-03")
+}
+
+fn next_month(first: NaiveDate) -> NaiveDate {
+    if first.month() == 12 {
+        NaiveDate::from_ymd(first.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd(first.year(), first.month() + 1, 1)
+    }
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn serialize_naive<S>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(&date.format("%Y-%m-%d").to_string())
+}
+
+/// Lay the month out in weeks starting on Monday. Each day shows how many
+/// items are due, with `*` marking today and `!` marking days with overdue
+/// items.
+fn grid(first: NaiveDate, days: &[Day]) -> String {
+    let mut out = String::new();
+
+    // writing to a String can't fail
+    let title = format!("{:^42}", first.format("%B %Y").to_string());
+    let _ = writeln!(out, "{}", title.trim_end());
+    let _ = writeln!(out, " Mon   Tue   Wed   Thu   Fri   Sat   Sun");
+
+    let mut week = "      ".repeat(first.weekday().num_days_from_monday() as usize);
+    for day in days {
+        let mark = if day.today {
+            '*'
+        } else if day.overdue {
+            '!'
+        } else {
+            ' '
+        };
+        let count = if day.count > 0 {
+            day.count.to_string()
+        } else {
+            String::new()
+        };
+
+        let _ = write!(week, "{:>3}{mark}{count:<2}", day.date.day());
+
+        if day.date.weekday() == chrono::Weekday::Sun {
+            let _ = writeln!(out, "{}", week.trim_end());
+            week.clear();
+        }
+    }
+    if !week.is_empty() {
+        let _ = writeln!(out, "{}", week.trim_end());
+    }
+
+    let _ = writeln!(out, "\n(* is today, ! has overdue items)");
+
+    out
+}
+
+fn list(days: &[Day]) -> String {
+    let mut out = String::new();
+
+    for day in days {
+        let Some(items) = day.items.as_ref().filter(|items| !items.is_empty()) else {
+            continue;
+        };
+
+        let _ = writeln!(out, "\n{}", day.date.format("%A, %B %d"));
+        for item in items {
+            let _ = writeln!(out, "  {}: {}", item.id, item.text);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pid::Pid;
+
+    fn occurrence(id: u64, date: Date) -> Occurrence {
+        Occurrence {
+            date,
+            item: Item {
+                id,
+                text: format!("item {id}"),
+                tag_id: None,
+                cadence: Cadence::days(1),
+                next: date,
+                pid: Pid::default(),
+                archived: false,
+                paused_until: None,
+                learning_steps: 0,
+            },
+        }
+    }
+
+    fn command(args: &[&str]) -> Command {
+        Command::try_parse_from(std::iter::once(&"calendar").chain(args)).unwrap()
+    }
+
+    #[test]
+    fn parses_months() {
+        assert_eq!(
+            Some(NaiveDate::from_ymd(2022, 3, 1)),
+            command(&["2022-03"]).month
+        );
+        assert!(Command::try_parse_from(["calendar", "2022-13"]).is_err());
+    }
+
+    #[test]
+    fn wraps_december() {
+        assert_eq!(
+            NaiveDate::from_ymd(2023, 1, 1),
+            next_month(NaiveDate::from_ymd(2022, 12, 1))
+        );
+    }
+
+    #[test]
+    fn counts_and_marks_days() {
+        let first = NaiveDate::from_ymd(2022, 3, 1);
+        let today = NaiveDate::from_ymd(2022, 3, 10);
+        let occurrences = vec![
+            occurrence(1, Date::ymd(2022, 3, 5)),
+            occurrence(2, Date::ymd(2022, 3, 20)),
+            occurrence(3, Date::ymd(2022, 3, 20)),
+        ];
+        let expected: Vec<(NaiveDate, usize)> = occurrences
+            .iter()
+            .map(|occurrence| (occurrence.date.local(), 1))
+            .collect();
+
+        let days = command(&["--list"]).days(first, today, occurrences);
+
+        assert_eq!(31, days.len());
+        assert!(days[9].today);
+        for (date, _) in expected {
+            let day = days.iter().find(|day| day.date == date).unwrap();
+            assert!(day.count > 0);
+            assert_eq!(date < today, day.overdue);
+            assert_eq!(day.count, day.items.as_ref().unwrap().len());
+        }
+    }
+
+    #[test]
+    fn draws_weeks_from_monday() {
+        // March 2022 started on a Tuesday
+        let first = NaiveDate::from_ymd(2022, 3, 1);
+        let mut days = command(&[]).days(first, NaiveDate::from_ymd(2022, 3, 2), Vec::new());
+        days[0].count = 3;
+        days[0].overdue = true;
+
+        let out = grid(first, &days);
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!("        1!3   2*    3     4     5     6", lines[2]);
+        assert_eq!("  7     8     9    10    11    12    13", lines[3]);
+    }
+}
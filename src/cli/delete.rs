@@ -1,5 +1,7 @@
 use crate::format::Format;
-use anyhow::{bail, Context, Result};
+use crate::item::Item;
+use crate::trash::Trashed;
+use anyhow::{Context, Result};
 use clap::Parser;
 use rusqlite::Connection;
 
@@ -11,22 +13,18 @@ pub struct Command {
 
 impl Command {
     pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
-        match conn.execute("DELETE FROM items WHERE id = ?", [self.id]) {
-            Ok(0) => bail!("Could not find item with ID {}", self.id),
-            Ok(1) => match format {
-                Format::Human => println!("Deleted item with ID {}", self.id),
-                Format::Json => println!("{}", serde_json::to_string(&true)?),
-            },
-            Ok(more_than_one) => {
-                bail!(
-                    "Deleted {} rows for ID {}. Please report this as a bug!",
-                    more_than_one,
-                    self.id
-                )
-            }
-            Err(err) => {
-                return Err(err).with_context(|| format!("could not drop item with ID {}", self.id))
-            }
+        let item = Item::get(self.id, conn)
+            .with_context(|| format!("Could not find item with ID {}", self.id))?;
+
+        Trashed::trash(&item, conn)
+            .with_context(|| format!("could not drop item with ID {}", self.id))?;
+
+        match format {
+            Format::Human => println!(
+                "Moved item with ID {} to the trash. Use `tempo trash restore {}` to bring it back",
+                self.id, self.id
+            ),
+            Format::Json => println!("{}", serde_json::to_string(&true)?),
         }
 
         Ok(())
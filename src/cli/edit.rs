@@ -14,7 +14,7 @@ pub struct Command {
 
     /// New text to for the item. New text is required if there are no
     /// other edits in the flags.
-    #[clap(required_unless_present_any(&["tag", "next", "cadence", "bump"]))]
+    #[clap(required_unless_present_any(&["tag", "next", "cadence", "bump", "learning-steps"]))]
     text: Vec<String>,
 
     /// Change this item's tag
@@ -32,6 +32,11 @@ pub struct Command {
     /// Tweak this item's schedule a little earlier or later
     #[clap(long, short, arg_enum, conflicts_with_all(&["cadence", "next"]))]
     bump: Option<Bump>,
+
+    /// Change how many more finishes will double or halve the cadence
+    /// before fine-tuning it (see add --help.) Use 0 to stop now.
+    #[clap(long)]
+    learning_steps: Option<u32>,
 }
 
 impl Command {
@@ -83,6 +88,14 @@ impl Command {
             }
         }
 
+        if let Some(learning_steps) = self.learning_steps {
+            item.learning_steps = learning_steps;
+
+            if format == Format::Human {
+                println!("Updated learning steps to {learning_steps}");
+            }
+        }
+
         item.save(conn)
             .with_context(|| format!("could not save item with ID {} to the database", self.id))?;
 
@@ -209,4 +222,30 @@ mod test {
         println!("before.next: {}, after.next: {}", before.next, after.next);
         assert!(before.next < after.next);
     }
+
+    #[test]
+    fn bumps_leave_learning_steps_alone() {
+        let conn = setup();
+        conn.execute(
+            "UPDATE items SET cadence = ?, learning_steps = 3 WHERE id = 1",
+            [Cadence::weeks(1)],
+        )
+        .unwrap();
+
+        let command = Command::try_parse_from(&["edit", "1", "--bump", "later"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        let after = Item::get(1, &conn).unwrap();
+        assert_eq!(3, after.learning_steps);
+        assert!(after.cadence < Cadence::weeks(2));
+    }
+
+    #[test]
+    fn updates_learning_steps() {
+        let conn = setup();
+        let command = Command::try_parse_from(&["edit", "1", "--learning-steps", "2"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        assert_eq!(2, Item::get(1, &conn).unwrap().learning_steps);
+    }
 }
@@ -0,0 +1,93 @@
+use crate::cadence::Cadence;
+use crate::export::Document;
+use crate::format::Format;
+use crate::ics;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// What to export to. "json" is everything in the store, and can be
+    /// read back in with `tempo import`. "ics" is an iCalendar file with an
+    /// all-day event for each item's next date, for calendar apps.
+    #[clap(long, arg_enum, default_value = "json")]
+    to: Target,
+
+    /// Only export items with these tags (ICS only)
+    #[clap(long, short)]
+    tag: Vec<String>,
+
+    /// Also add events for every time an item would come up again within
+    /// this long from today, assuming its cadence stays the same (ICS only.)
+    /// Supports the same units as `add --cadence`.
+    #[clap(long)]
+    horizon: Option<Cadence>,
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq)]
+enum Target {
+    Json,
+    Ics,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        match self.to {
+            Target::Json => {
+                if !self.tag.is_empty() || self.horizon.is_some() {
+                    bail!("--tag and --horizon only work when exporting to ICS")
+                }
+
+                let document = Document::from_store(conn).context("could not export the store")?;
+
+                // the export is always JSON, since the point is to read it
+                // back in with `tempo import`. We just make it easier to
+                // read if you're looking at it on the command line.
+                let out = match format {
+                    Format::Human => serde_json::to_string_pretty(&document),
+                    Format::Json => serde_json::to_string(&document),
+                }
+                .context("could not convert the export to JSON")?;
+
+                println!("{out}");
+            }
+            Target::Ics => print!(
+                "{}",
+                ics::calendar(conn, &self.tag, self.horizon)
+                    .context("could not export the calendar")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn
+    }
+
+    #[test]
+    fn ics_options_require_ics() {
+        let command = Command::try_parse_from(["export", "--horizon", "1m"]).unwrap();
+
+        assert!(command.run(&conn(), Format::Human).is_err());
+    }
+
+    #[test]
+    fn exports_ics() {
+        let command =
+            Command::try_parse_from(["export", "--to", "ics", "--horizon", "1m"]).unwrap();
+
+        assert!(command.run(&conn(), Format::Human).is_ok());
+    }
+}
@@ -1,8 +1,12 @@
 use crate::format::Format;
+use crate::history::Finish;
 use crate::item::{Bump, Item};
-use anyhow::{Context, Result};
-use clap::Parser;
+use crate::retire::Policy;
+use anyhow::{bail, Context, Result};
+use clap::{ArgEnum, Parser};
+use crossterm::tty::IsTty;
 use rusqlite::Connection;
+use std::io::{BufRead, Write};
 
 #[derive(Parser, Debug)]
 pub struct Command {
@@ -11,33 +15,233 @@ pub struct Command {
     id: u64,
 
     /// Whether this item was scheduled too early, too late, etc. We will
-    /// use this feedback to schedule the next repetition of the item.
+    /// use this feedback to schedule the next repetition of the item. If
+    /// you leave this off, we'll show you the options and ask (but only in
+    /// an interactive terminal.)
     #[clap(arg_enum)]
-    bump: Bump,
+    bump: Option<Bump>,
 }
 
 impl Command {
-    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+    /// Figure out how it went, asking if the bump wasn't given on the
+    /// command line. Answering can take as long as it takes, so this runs
+    /// before `run` takes the write lock, to keep from holding up other
+    /// Tempo processes while we wait.
+    pub fn bump(&self, conn: &Connection, format: Format) -> Result<Bump> {
+        if let Some(bump) = &self.bump {
+            return Ok(bump.clone());
+        }
+
+        if format == Format::Human && std::io::stdin().is_tty() {
+            let item = Item::get(self.id, conn)
+                .with_context(|| format!("couldn't load item with ID {}", self.id))?;
+
+            // no sense asking how it went if we can't finish it anyway
+            item.clone()
+                .finish(&Bump::JustRight)
+                .with_context(|| format!("couldn't finish item with ID {}", self.id))?;
+
+            return prompt(&item, std::io::stdin().lock(), std::io::stdout());
+        }
+
+        bail!(
+            "I need to know how this went. Add one of {} after the ID",
+            names().join(", ")
+        )
+    }
+
+    pub fn run(&self, conn: &Connection, format: Format, bump: &Bump) -> Result<()> {
         let mut item = Item::get(self.id, conn)
             .with_context(|| format!("couldn't load item with ID {}", self.id))?;
 
+        let due = item.next;
         let adjustment = item
-            .finish(&self.bump)
+            .finish(bump)
             .with_context(|| format!("couldn't finish item with ID {}", self.id))?;
 
         item.save(conn)
             .with_context(|| format!("couldn't save item with ID {}", self.id))?;
+        Finish::record(conn, &item, bump, due)?;
 
         match format {
-            Format::Human =>
+            Format::Human => {
                 println!(
                     "Finished! For next time, I bumped the schedule by {} so the next time you'll see this will be {}",
                     adjustment,
                     item.next
-                ),
-            Format::Json => println!("{}", serde_json::to_string(&item).context("couldn't convert item to JSON")?),
+                );
+
+                // this is only a hint, so a bad policy in the environment
+                // shouldn't undo a finish that already worked.
+                match Policy::from_env().and_then(|policy| policy.check(&item, conn)) {
+                    Ok(Some(_)) => println!("This keeps getting pushed away. If you'd rather not see it anymore, `tempo retire --review` can archive it"),
+                    Ok(None) => (),
+                    Err(err) => log::warn!("couldn't check whether to suggest retiring this item: {err:#}"),
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&item).context("couldn't convert item to JSON")?
+            ),
         }
 
         Ok(())
     }
 }
+
+fn names() -> Vec<&'static str> {
+    Bump::value_variants()
+        .iter()
+        .filter_map(ArgEnum::to_possible_value)
+        .map(|value| value.get_name())
+        .collect()
+}
+
+/// Show the item and what finishing it with each bump would do, then ask
+/// which one to use until we get an answer we understand.
+fn prompt(item: &Item, mut input: impl BufRead, mut out: impl Write) -> Result<Bump> {
+    let bumps = Bump::value_variants();
+    let names = names();
+
+    writeln!(out, "{}: {} (due {})", item.id, item.text, item.next)?;
+    writeln!(out)?;
+    for (number, (bump, name)) in (1..).zip(bumps.iter().zip(&names)) {
+        let mut preview = item.clone();
+        preview.finish(bump)?;
+
+        writeln!(out, "  {number}) {name:<12} next on {}", preview.next)?;
+    }
+    writeln!(out)?;
+
+    loop {
+        write!(out, "How did it go? [1-{}] ", bumps.len())?;
+        out.flush()?;
+
+        let mut answer = String::new();
+        if input
+            .read_line(&mut answer)
+            .context("couldn't read an answer")?
+            == 0
+        {
+            bail!("didn't get an answer, so I didn't finish the item")
+        }
+        let answer = answer.trim();
+
+        let chosen = match answer.parse::<usize>() {
+            Ok(number) => number
+                .checked_sub(1)
+                .and_then(|index| bumps.get(index))
+                .cloned(),
+            Err(_) => Bump::from_str(answer, true).ok(),
+        };
+
+        match chosen {
+            Some(bump) => return Ok(bump),
+            None => writeln!(
+                out,
+                "Sorry, I don't know \"{answer}\". Pick a number, or one of {}",
+                names.join(", ")
+            )?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cadence::Cadence;
+    use crate::date::Date;
+    use crate::pid::Pid;
+
+    fn item() -> Item {
+        Item {
+            id: 1,
+            text: "Test".into(),
+            tag_id: None,
+            cadence: Cadence::days(7),
+            next: Date::today(),
+            pid: Pid::default(),
+            archived: false,
+            paused_until: None,
+            learning_steps: 0,
+        }
+    }
+
+    fn ask(input: &str) -> (Result<Bump>, String) {
+        let mut out = Vec::new();
+        let bump = prompt(&item(), input.as_bytes(), &mut out);
+
+        (bump, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn shows_projected_dates() {
+        let (_, out) = ask("3\n");
+
+        assert!(out.contains(&format!(
+            "3) just-right   next on {}",
+            Date::today() + Cadence::days(7)
+        )));
+        assert!(out.contains("5) much-later"));
+    }
+
+    #[test]
+    fn accepts_numbers() {
+        assert!(matches!(ask("1\n").0.unwrap(), Bump::MuchEarlier));
+    }
+
+    #[test]
+    fn accepts_names() {
+        assert!(matches!(ask("Later\n").0.unwrap(), Bump::Later));
+    }
+
+    #[test]
+    fn asks_again_after_nonsense() {
+        let (bump, out) = ask("6\nsure\n2\n");
+
+        assert!(matches!(bump.unwrap(), Bump::Earlier));
+        assert_eq!(2, out.matches("Sorry").count());
+    }
+
+    #[test]
+    fn gives_up_without_an_answer() {
+        assert!(ask("").0.is_err());
+    }
+
+    #[test]
+    fn json_requires_a_bump() {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (\"due\", ?, ?)",
+            rusqlite::params![Cadence::days(1), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+
+        let command = Command::try_parse_from(["finish", "1"]).unwrap();
+
+        assert!(command.bump(&conn, Format::Json).is_err());
+        assert_eq!(Date::ymd(2022, 1, 1), Item::get(1, &conn).unwrap().next);
+    }
+
+    #[test]
+    fn finishes_with_the_chosen_bump() {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (\"due\", ?, ?)",
+            rusqlite::params![Cadence::days(1), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+
+        let command = Command::try_parse_from(["finish", "1", "just-right"]).unwrap();
+        let bump = command.bump(&conn, Format::Json).unwrap();
+        command.run(&conn, Format::Json, &bump).unwrap();
+
+        assert_eq!(1, Finish::all(&conn).unwrap().count());
+    }
+}
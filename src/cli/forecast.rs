@@ -0,0 +1,163 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::forecast::{self, Occurrence};
+use crate::format::Format;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The widest a bar in the chart can get before we start scaling.
+static MAX_BAR_WIDTH: usize = 50;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// How many days to look ahead, starting today
+    #[clap(long, default_value = "30")]
+    days: i64,
+
+    /// Only count items with these tags
+    #[clap(long, short)]
+    tag: Vec<String>,
+
+    /// Also count later repetitions inside the window, assuming each one
+    /// gets finished on time with "just-right" feedback
+    #[clap(long, short)]
+    repeat: bool,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        if self.days < 1 {
+            bail!("--days has to be at least 1")
+        }
+
+        let today = Date::today();
+        let until = today + Cadence::days(self.days);
+        let occurrences = forecast::occurrences(conn, &self.tag, until, self.repeat)?;
+        let counts = count(today, until, &occurrences);
+
+        match format {
+            Format::Human => print!("{}", chart(&counts)),
+            Format::Json => {
+                let by_date: BTreeMap<String, usize> = counts
+                    .iter()
+                    .map(|(date, count)| (date.format("%Y-%m-%d"), *count))
+                    .collect();
+
+                println!(
+                    "{}",
+                    serde_json::to_string(&by_date).context("couldn't convert forecast to JSON")?
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Count how many items come due each day from `today` up to (but not
+/// including) `until`. Overdue items count as due today, since that's when
+/// you'll see them.
+fn count(today: Date, until: Date, occurrences: &[Occurrence]) -> Vec<(Date, usize)> {
+    let mut counts = Vec::new();
+    let mut date = today;
+    while date < until {
+        counts.push((date, 0));
+        date = date + Cadence::days(1);
+    }
+
+    for occurrence in occurrences {
+        let index = (occurrence.date - today).num_days().max(0);
+        if let Some((_, count)) = usize::try_from(index)
+            .ok()
+            .and_then(|index| counts.get_mut(index))
+        {
+            *count += 1;
+        }
+    }
+
+    counts
+}
+
+fn chart(counts: &[(Date, usize)]) -> String {
+    let most = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let width = |count: usize| {
+        if most <= MAX_BAR_WIDTH {
+            count
+        } else {
+            (count * MAX_BAR_WIDTH).div_ceil(most)
+        }
+    };
+
+    let mut out = String::new();
+    for (date, count) in counts {
+        // writing to a String can't fail
+        let _ = writeln!(
+            out,
+            "{} {:>4} {}",
+            date.format("%a %b %d"),
+            count,
+            "#".repeat(width(*count))
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::Item;
+    use crate::pid::Pid;
+
+    fn occurrence(date: Date) -> Occurrence {
+        Occurrence {
+            date,
+            item: Item {
+                id: 1,
+                text: "test".into(),
+                tag_id: None,
+                cadence: Cadence::days(1),
+                next: date,
+                pid: Pid::default(),
+                archived: false,
+                paused_until: None,
+                learning_steps: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn counts_overdue_items_today() {
+        let today = Date::ymd(2022, 1, 10);
+        let counts = count(
+            today,
+            today + Cadence::days(3),
+            &[
+                occurrence(Date::ymd(2022, 1, 1)),
+                occurrence(Date::ymd(2022, 1, 10)),
+                occurrence(Date::ymd(2022, 1, 12)),
+            ],
+        );
+
+        assert_eq!(
+            vec![
+                (Date::ymd(2022, 1, 10), 2),
+                (Date::ymd(2022, 1, 11), 0),
+                (Date::ymd(2022, 1, 12), 1),
+            ],
+            counts
+        );
+    }
+
+    #[test]
+    fn scales_wide_bars() {
+        let out = chart(&[(Date::ymd(2022, 1, 10), 200), (Date::ymd(2022, 1, 11), 4)]);
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(format!("Mon Jan 10  200 {}", "#".repeat(50)), lines[0]);
+        assert_eq!("Tue Jan 11    4 #", lines[1]);
+    }
+}
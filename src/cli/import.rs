@@ -0,0 +1,346 @@
+use crate::bulk::{self, Row, RowError};
+use crate::export::{Change, Document, Mode};
+use crate::format::Format;
+use crate::item::{Item, DEFAULT_LEARNING_STEPS};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+use std::io::Read;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The file to import. Use "-" to read from stdin.
+    path: PathBuf,
+
+    /// What kind of file this is. "json" is the output of `tempo export`.
+    /// "csv" needs a header row with a "text" column, and can also have
+    /// "tag", "cadence", "next", and "url" columns. "markdown" adds every
+    /// list item, tagged with the heading it's under.
+    #[clap(long, arg_enum, default_value = "json")]
+    from: Source,
+
+    /// Replace everything in the store with the contents of the file instead
+    /// of merging items in by ID (JSON only)
+    #[clap(long)]
+    replace: bool,
+
+    /// Add items even if there's already one with the same text (CSV and
+    /// Markdown only)
+    #[clap(long)]
+    allow_duplicate: bool,
+
+    /// How many learning steps new items start with, as in `tempo add`
+    /// (CSV and Markdown only)
+    #[clap(long, env = "TEMPO_LEARNING_STEPS", default_value_t = DEFAULT_LEARNING_STEPS)]
+    learning_steps: u32,
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq)]
+enum Source {
+    Json,
+    Csv,
+    Markdown,
+}
+
+#[derive(serde::Serialize)]
+struct BulkOutput<'a> {
+    added: &'a [Item],
+    errors: &'a [RowError],
+}
+
+impl Command {
+    /// Import the file. With `dry_run` (the global `--dry-run` flag), show
+    /// what would change instead; the caller rolls back afterwards either
+    /// way, but this lets us say "would add" instead of "added".
+    pub fn run(&self, conn: &Connection, format: Format, dry_run: bool) -> Result<()> {
+        let contents = self.read()?;
+
+        match self.from {
+            Source::Json => self.run_json(&contents, conn, format, dry_run),
+            Source::Csv => self.run_bulk(bulk::parse_csv(&contents), conn, format, dry_run),
+            Source::Markdown => {
+                self.run_bulk(bulk::parse_markdown(&contents), conn, format, dry_run)
+            }
+        }
+    }
+
+    fn run_json(
+        &self,
+        contents: &str,
+        conn: &Connection,
+        format: Format,
+        dry_run: bool,
+    ) -> Result<()> {
+        let document: Document =
+            serde_json::from_str(contents).context("could not parse the export")?;
+
+        let mode = if self.replace {
+            Mode::Replace
+        } else {
+            Mode::Merge
+        };
+
+        let changes = if dry_run {
+            document.plan(mode, conn)?
+        } else {
+            document.import(mode, conn)?
+        };
+
+        match format {
+            Format::Human => {
+                for change in &changes {
+                    match change {
+                        Change::Added { id, text } => println!("added {id}: {text}"),
+                        Change::Updated { id, text } => println!("updated {id}: {text}"),
+                        Change::Removed { id, text } => println!("removed {id}: {text}"),
+                    }
+                }
+
+                if dry_run {
+                    println!("Would change {} item(s)", changes.len());
+                } else {
+                    println!("Changed {} item(s)", changes.len());
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&changes).context("could not convert changes to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+
+    fn run_bulk(
+        &self,
+        rows: Vec<Result<Row, RowError>>,
+        conn: &Connection,
+        format: Format,
+        dry_run: bool,
+    ) -> Result<()> {
+        if self.replace {
+            bail!("--replace only works when importing JSON")
+        }
+
+        let mut added = Vec::new();
+        let mut errors = Vec::new();
+
+        // We add everything for real (even in a dry run) so that we catch
+        // problems like duplicates within the file itself, then throw it
+        // all away if this is a dry run or anything went wrong.
+        conn.execute_batch("SAVEPOINT bulk_import")
+            .context("could not start the import")?;
+
+        for row in rows {
+            match row.and_then(|row| {
+                row.insert(conn, self.allow_duplicate, self.learning_steps)
+                    .map_err(|err| RowError {
+                        line: row.line,
+                        message: format!("{err:#}"),
+                    })
+            }) {
+                Ok(item) => added.push(item),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        if dry_run || !errors.is_empty() {
+            conn.execute_batch("ROLLBACK TO bulk_import")
+                .context("could not roll back the import")?;
+        }
+
+        // if anything went wrong, nothing was added, so don't report the
+        // rows we rolled back (or the IDs they briefly had.)
+        if !errors.is_empty() {
+            added.clear();
+        }
+        conn.execute_batch("RELEASE bulk_import")
+            .context("could not finish the import")?;
+
+        match format {
+            Format::Human => {
+                let verb = if dry_run { "would add" } else { "added" };
+                for item in &added {
+                    println!("{} {}: {}", verb, item.id, item.text);
+                }
+
+                for error in &errors {
+                    println!("{error}");
+                }
+
+                if errors.is_empty() && dry_run {
+                    println!("Would add {} item(s)", added.len());
+                } else if errors.is_empty() {
+                    println!("Added {} item(s)", added.len());
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&BulkOutput {
+                    added: &added,
+                    errors: &errors,
+                })
+                .context("could not convert the import results to JSON")?
+            ),
+        }
+
+        if !errors.is_empty() {
+            bail!(
+                "found {} problem(s), so nothing was imported. Fix them and try again",
+                errors.len()
+            )
+        }
+
+        Ok(())
+    }
+
+    fn read(&self) -> Result<String> {
+        let mut contents = String::new();
+
+        if self.path.to_str() == Some("-") {
+            std::io::stdin()
+                .read_to_string(&mut contents)
+                .context("could not read from stdin")?;
+        } else {
+            contents = std::fs::read_to_string(&self.path)
+                .with_context(|| format!("could not read {}", self.path.display()))?;
+        }
+
+        Ok(contents)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn
+    }
+
+    fn write_file(name: &str, contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("tempo-test-{}-import-{}", std::process::id(), name));
+
+        std::fs::write(&path, contents).unwrap();
+
+        path
+    }
+
+    fn count(conn: &Connection) -> u64 {
+        conn.query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn imports_file() {
+        let conn = conn();
+        let path = write_file(
+            "file.json",
+            r#"{"version":1,"tags":["tag"],"items":[{"id":3,"text":"imported","tag":"tag","cadence":{"days":7},"next":"2022-01-01","integral":0.5,"last_error":1.0}]}"#,
+        );
+
+        let command = Command::try_parse_from(["import", path.to_str().unwrap()]).unwrap();
+        command.run(&conn, Format::Human, false).unwrap();
+
+        let item = Item::get(3, &conn).unwrap();
+        assert_eq!("imported", item.text);
+        assert!(item.tag_id.is_some());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn dry_run_changes_nothing() {
+        let conn = conn();
+        let path = write_file(
+            "dry-run.json",
+            r#"{"version":1,"tags":[],"items":[{"id":3,"text":"imported","tag":null,"cadence":{"days":7},"next":"2022-01-01","integral":0.5,"last_error":1.0}]}"#,
+        );
+
+        let command = Command::try_parse_from(["import", path.to_str().unwrap()]).unwrap();
+        command.run(&conn, Format::Human, true).unwrap();
+
+        assert!(Item::get(3, &conn).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn imports_csv() {
+        let conn = conn();
+        let path = write_file("rows.csv", "text,tag\nfirst,a\nsecond,b\n");
+
+        let command =
+            Command::try_parse_from(["import", path.to_str().unwrap(), "--from", "csv"]).unwrap();
+        command.run(&conn, Format::Human, false).unwrap();
+
+        assert_eq!(2, count(&conn));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn imports_markdown() {
+        let conn = conn();
+        let path = write_file("list.md", "# tag\n- first\n- second\n");
+
+        let command =
+            Command::try_parse_from(["import", path.to_str().unwrap(), "--from", "markdown"])
+                .unwrap();
+        command.run(&conn, Format::Human, false).unwrap();
+
+        assert_eq!(2, count(&conn));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bulk_errors_import_nothing() {
+        let conn = conn();
+        let path = write_file("errors.csv", "text,cadence\nfirst,1d\nsecond,whenever\n");
+
+        let command =
+            Command::try_parse_from(["import", path.to_str().unwrap(), "--from", "csv"]).unwrap();
+
+        assert!(command.run(&conn, Format::Human, false).is_err());
+        assert_eq!(0, count(&conn));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bulk_catches_duplicates_within_the_file() {
+        let conn = conn();
+        let path = write_file("duplicates.md", "- same\n- Same\n");
+
+        let command =
+            Command::try_parse_from(["import", path.to_str().unwrap(), "--from", "markdown"])
+                .unwrap();
+
+        assert!(command.run(&conn, Format::Human, false).is_err());
+        assert_eq!(0, count(&conn));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bulk_dry_run_changes_nothing() {
+        let conn = conn();
+        let path = write_file("dry-run.csv", "text\nfirst\n");
+
+        let command =
+            Command::try_parse_from(["import", path.to_str().unwrap(), "--from", "csv"]).unwrap();
+        command.run(&conn, Format::Human, true).unwrap();
+
+        assert_eq!(0, count(&conn));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,9 +1,27 @@
 pub mod add;
 pub mod all;
+pub mod archive;
+pub mod backup;
+pub mod calendar;
 pub mod delete;
 pub mod edit;
+pub mod export;
 pub mod finish;
+pub mod forecast;
+pub mod import;
+pub mod pause;
 pub mod ready;
+pub mod reschedule;
+pub mod restore;
+pub mod retire;
+pub mod review;
+pub mod rpc;
+pub mod serve;
+pub mod show;
+pub mod stats;
+pub mod trash;
+pub mod unarchive;
+pub mod vacation;
 
 use crate::cadence::Cadence;
 use crate::date::Date;
@@ -11,7 +29,7 @@ use anyhow::{Context, Result};
 use chrono::{Local, TimeZone, Utc};
 use std::str::FromStr;
 
-fn parse_utc_datetime(input: &str) -> Result<Date> {
+pub fn parse_utc_datetime(input: &str) -> Result<Date> {
     let base = if input == "today" {
         Local::now()
     } else {
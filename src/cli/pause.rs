@@ -0,0 +1,87 @@
+use crate::date::Date;
+use crate::format::Format;
+use crate::item::Item;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// ID of the item to pause
+    id: u64,
+
+    /// Keep the item out of "ready" until this date. Accepts the same
+    /// values as `add --next`.
+    #[clap(long, short, parse(try_from_str = super::parse_utc_datetime))]
+    until: Date,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        let mut item = Item::get(self.id, conn)
+            .with_context(|| format!("couldn't load item with ID {}", self.id))?;
+
+        if item.archived {
+            bail!(
+                "item with ID {} is archived. Unarchive it before pausing it",
+                self.id
+            )
+        }
+
+        item.paused_until = Some(self.until);
+
+        item.save(conn)
+            .with_context(|| format!("couldn't save item with ID {}", self.id))?;
+
+        match format {
+            Format::Human => println!("Paused item with ID {} until {}", self.id, self.until),
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&item).context("couldn't convert item to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cadence::Cadence;
+    use rusqlite::params;
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::runner().run(&mut conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params!["test", Cadence::days(1), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn pauses_item() {
+        let conn = setup();
+        let command = Command::try_parse_from(&["pause", "1", "--until", "2022-03-01"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        let item = Item::get(1, &conn).unwrap();
+        assert_eq!(Some(Date::ymd(2022, 3, 1)), item.paused_until);
+        assert_eq!(Date::ymd(2022, 1, 1), item.next);
+    }
+
+    #[test]
+    fn fails_for_archived_items() {
+        let conn = setup();
+        conn.execute("UPDATE items SET archived = 1 WHERE id = 1", [])
+            .unwrap();
+
+        let command = Command::try_parse_from(&["pause", "1", "--until", "2022-03-01"]).unwrap();
+        assert!(command.run(&conn, Format::Human).is_err());
+    }
+}
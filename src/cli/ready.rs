@@ -1,10 +1,15 @@
+use crate::date::Date;
 use crate::format::Format;
 use crate::item::Item;
+use crate::sort::Sort;
+use crate::status::Status;
 use crate::tag::Tag;
+use crate::vacation::Vacation;
 use anyhow::{Context, Result};
 use clap::Parser;
 use rusqlite::Connection;
 use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Parser)]
 pub struct Command {
@@ -15,17 +20,46 @@ pub struct Command {
     /// Only get items with these tags
     #[clap(long, short)]
     tag: Option<Vec<String>>,
+
+    /// Only get items that are still learning their schedule (that is,
+    /// leave out items that have gotten "just-right" feedback for a while)
+    #[clap(long)]
+    learning_only: bool,
+
+    /// Include archived items, which are otherwise hidden
+    #[clap(long)]
+    include_archived: bool,
+
+    /// What order to put the items in. The limit applies after sorting.
+    #[clap(long, short, arg_enum, default_value = "next")]
+    sort: Sort,
+
+    /// Seed for `--sort random`. Pass the same one to get the same order
+    /// again; otherwise we pick a new one every time (and print it, so you
+    /// can.)
+    #[clap(long)]
+    seed: Option<u64>,
 }
 
 impl Command {
     pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
-        let pulled = self.items(conn)?;
+        let seed = self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+        });
+        let pulled = self.items(conn, seed)?;
 
         match format {
             Format::Human => {
                 for item in pulled {
                     println!("{}: {} (due {})", item.id, item.text, item.next);
                 }
+
+                // a seed we picked is no use to anyone unless we say what it was
+                if self.sort == Sort::Random && self.seed.is_none() {
+                    println!("(Shuffled with --seed {seed}. Pass it again to get the same order)");
+                }
             }
             Format::Json => println!(
                 "{}",
@@ -36,42 +70,74 @@ impl Command {
         Ok(())
     }
 
-    fn items(&self, conn: &Connection) -> Result<Vec<Item>> {
-        let tag_ids: Option<HashSet<u64>> = match &self.tag {
-            Some(tag_names) => Some(
-                Tag::all(conn)
-                    .context("couldn't get tags")?
-                    .filter(|tag| tag_names.contains(&tag.name))
-                    .map(|tag| tag.id)
-                    .collect(),
-            ),
+    fn items(&self, conn: &Connection, seed: u64) -> Result<Vec<Item>> {
+        let mut items = query(conn, self.tag.as_deref(), None, self.include_archived)?;
 
-            None => None,
-        };
+        if self.learning_only {
+            let mut learning = Vec::new();
+            for item in items {
+                if Status::of(&item, conn)? == Status::Learning {
+                    learning.push(item);
+                }
+            }
+            items = learning;
+        }
 
-        // note to future explorers: seems like we could do this with a SELECT,
-        // right? Well, how many items are we ever gonna have? It's probably
-        // under 1,000, so we're not going to see a huge speed benefit (computers
-        // are fast!) and we'd probably have to introduce some query builder
-        // dependency as well. Let's see how far we can take the naive pattern!
-        let items = Item::due(conn)
-            .context("couldn't get items from the database")?
-            .filter(|item| match &tag_ids {
-                Some(ids) => item.tag_id.as_ref().map_or(false, |id| ids.contains(id)),
-                None => true,
-            })
-            .take(self.limit.unwrap_or(usize::MAX))
-            .collect();
+        let vacations: Vec<Vacation> = Vacation::all(conn)?.collect();
 
-        Ok(items)
+        Ok(self
+            .sort
+            .apply(items, Date::today(), &vacations, seed)
+            .into_iter()
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect())
     }
 }
 
+/// Get the items that are due, optionally limited to some tags. This is
+/// shared with `tempo serve`, so that the two always agree.
+pub fn query(
+    conn: &Connection,
+    tags: Option<&[String]>,
+    limit: Option<usize>,
+    include_archived: bool,
+) -> Result<Vec<Item>> {
+    let tag_ids: Option<HashSet<u64>> = match tags {
+        Some(tag_names) => Some(
+            Tag::all(conn)
+                .context("couldn't get tags")?
+                .filter(|tag| tag_names.contains(&tag.name))
+                .map(|tag| tag.id)
+                .collect(),
+        ),
+
+        None => None,
+    };
+
+    // note to future explorers: seems like we could do this with a SELECT,
+    // right? Well, how many items are we ever gonna have? It's probably
+    // under 1,000, so we're not going to see a huge speed benefit (computers
+    // are fast!) and we'd probably have to introduce some query builder
+    // dependency as well. Let's see how far we can take the naive pattern!
+    let items = Item::due(conn, include_archived)
+        .context("couldn't get items from the database")?
+        .filter(|item| match &tag_ids {
+            Some(ids) => item.tag_id.as_ref().map_or(false, |id| ids.contains(id)),
+            None => true,
+        })
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+
+    Ok(items)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cadence::Cadence;
     use crate::date::Date;
+    use crate::history::Finish;
+    use crate::item::Bump;
     use rusqlite::params;
 
     fn conn() -> Connection {
@@ -94,11 +160,29 @@ mod tests {
         .unwrap();
 
         let command = Command::try_parse_from(&["pull"]).unwrap();
-        let items = command.items(&conn).unwrap();
+        let items = command.items(&conn, 0).unwrap();
 
         assert!(items.is_empty());
     }
 
+    #[test]
+    fn archived_only_when_asked() {
+        let conn = conn();
+
+        let cadence = Cadence::days(1);
+        conn.execute(
+            "INSERT INTO items (text, next, cadence, archived) VALUES (?, ?, ?, 1)",
+            params!["X", Date::today() - cadence, cadence],
+        )
+        .unwrap();
+
+        let command = Command::try_parse_from(&["pull"]).unwrap();
+        assert!(command.items(&conn, 0).unwrap().is_empty());
+
+        let command = Command::try_parse_from(&["pull", "--include-archived"]).unwrap();
+        assert_eq!(1, command.items(&conn, 0).unwrap().len());
+    }
+
     #[test]
     fn due() {
         let conn = conn();
@@ -112,7 +196,61 @@ mod tests {
         .unwrap();
 
         let command = Command::try_parse_from(&["pull"]).unwrap();
-        let items = command.items(&conn).unwrap();
+        let items = command.items(&conn, 0).unwrap();
+
+        assert_eq!(vec![Item::get(1, &conn).unwrap()], items);
+    }
+
+    #[test]
+    fn archived() {
+        let conn = conn();
+
+        let cadence = Cadence::days(1);
+        let next = Date::today() - cadence;
+        conn.execute(
+            "INSERT INTO items (text, next, cadence, archived) VALUES (?, ?, ?, ?)",
+            params!["X", next, cadence, true],
+        )
+        .unwrap();
+
+        let command = Command::try_parse_from(&["pull"]).unwrap();
+        let items = command.items(&conn, 0).unwrap();
+
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn paused() {
+        let conn = conn();
+
+        let cadence = Cadence::days(1);
+        let next = Date::today() - cadence;
+        conn.execute(
+            "INSERT INTO items (text, next, cadence, paused_until) VALUES (?, ?, ?, ?)",
+            params!["X", next, cadence, Date::today() + cadence],
+        )
+        .unwrap();
+
+        let command = Command::try_parse_from(&["pull"]).unwrap();
+        let items = command.items(&conn, 0).unwrap();
+
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn pause_expired() {
+        let conn = conn();
+
+        let cadence = Cadence::days(1);
+        let next = Date::today() - cadence;
+        conn.execute(
+            "INSERT INTO items (text, next, cadence, paused_until) VALUES (?, ?, ?, ?)",
+            params!["X", next, cadence, Date::today() - cadence],
+        )
+        .unwrap();
+
+        let command = Command::try_parse_from(&["pull"]).unwrap();
+        let items = command.items(&conn, 0).unwrap();
 
         assert_eq!(vec![Item::get(1, &conn).unwrap()], items);
     }
@@ -130,7 +268,7 @@ mod tests {
         .unwrap();
 
         let command = Command::try_parse_from(&["pull", "--tag", "x"]).unwrap();
-        let items = command.items(&conn).unwrap();
+        let items = command.items(&conn, 0).unwrap();
 
         assert!(items.is_empty());
     }
@@ -157,7 +295,7 @@ mod tests {
         .unwrap();
 
         let command = Command::try_parse_from(&["pull"]).unwrap();
-        let items = command.items(&conn).unwrap();
+        let items = command.items(&conn, 0).unwrap();
 
         assert_eq!(vec![Item::get(1, &conn).unwrap()], items);
     }
@@ -184,7 +322,7 @@ mod tests {
         .unwrap();
 
         let command = Command::try_parse_from(&["pull", "--tag", tag_name]).unwrap();
-        let items = command.items(&conn).unwrap();
+        let items = command.items(&conn, 0).unwrap();
 
         assert_eq!(vec![Item::get(1, &conn).unwrap()], items);
     }
@@ -212,7 +350,7 @@ mod tests {
         .unwrap();
 
         let command = Command::try_parse_from(&["pull", "--limit", "1"]).unwrap();
-        let items = command.items(&conn).unwrap();
+        let items = command.items(&conn, 0).unwrap();
 
         assert_eq!(vec![Item::get(2, &conn).unwrap()], items);
     }
@@ -240,11 +378,67 @@ mod tests {
         .unwrap();
 
         let command = Command::try_parse_from(&["pull"]).unwrap();
-        let items = command.items(&conn).unwrap();
+        let items = command.items(&conn, 0).unwrap();
 
         assert_eq!(
             vec![Item::get(1, &conn).unwrap(), Item::get(2, &conn).unwrap()],
             items
         );
     }
+
+    #[test]
+    fn learning_only() {
+        let conn = conn();
+        let cadence = Cadence::days(1);
+        let next = Date::today() - cadence;
+
+        for text in ["Learning", "Settled", "Also learning"] {
+            conn.execute(
+                "INSERT INTO items (text, next, cadence) VALUES (?, ?, ?)",
+                params![text, next, cadence],
+            )
+            .unwrap();
+        }
+
+        let settled = Item::get(2, &conn).unwrap();
+        for _ in 0..3 {
+            Finish::record(&conn, &settled, &Bump::JustRight, next).unwrap();
+        }
+
+        let command =
+            Command::try_parse_from(&["pull", "--learning-only", "--limit", "2"]).unwrap();
+        let items: Vec<u64> = command
+            .items(&conn, 0)
+            .unwrap()
+            .iter()
+            .map(|item| item.id)
+            .collect();
+
+        assert_eq!(vec![1, 3], items);
+    }
+
+    #[test]
+    fn limit_applies_after_sorting() {
+        let conn = conn();
+        let today = Date::today();
+
+        for (text, cadence, late) in [
+            ("Yearly", Cadence::years(1), 2),
+            ("Daily", Cadence::days(1), 1),
+        ] {
+            conn.execute(
+                "INSERT INTO items (text, next, cadence) VALUES (?, ?, ?)",
+                params![text, today - Cadence::days(late), cadence],
+            )
+            .unwrap();
+        }
+
+        let command =
+            Command::try_parse_from(&["pull", "--sort", "overdue-ratio", "--limit", "1"]).unwrap();
+
+        assert_eq!(
+            vec![Item::get(2, &conn).unwrap()],
+            command.items(&conn, 0).unwrap()
+        );
+    }
 }
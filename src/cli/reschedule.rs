@@ -0,0 +1,335 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::format::Format;
+use crate::item::Item;
+use crate::reschedule::{self, Filter};
+use crate::tag::Tag;
+use anyhow::{Context, Result};
+use clap::{ArgGroup, Parser};
+use crossterm::tty::IsTty;
+use rusqlite::Connection;
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+
+#[derive(Debug, Parser)]
+#[clap(group(ArgGroup::new("how").required(true).args(&["shift", "spread-over", "to"])))]
+pub struct Command {
+    /// Only move items with this tag
+    #[clap(long, short)]
+    tag: Option<String>,
+
+    /// Only move items matching a condition on next, cadence, or status,
+    /// like "next<2022-03-01", "cadence>=2w", or "status=learning". Can be
+    /// given more than once, in which case items have to match all of them.
+    #[clap(long = "where", short = 'w')]
+    filters: Vec<Filter>,
+
+    /// Move each item later by this much
+    #[clap(long)]
+    shift: Option<Cadence>,
+
+    /// Spread the items out evenly over this long, starting today (in the
+    /// order they were due)
+    #[clap(long)]
+    spread_over: Option<Cadence>,
+
+    /// Move every item to this date. Accepts the same values as
+    /// `add --next`.
+    #[clap(long, parse(try_from_str = super::parse_utc_datetime))]
+    to: Option<Date>,
+
+    /// Make the changes without asking first
+    #[clap(long, short)]
+    yes: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Change {
+    #[serde(with = "crate::date::ymd")]
+    from: Date,
+    item: Item,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Output {
+    applied: bool,
+    changes: Vec<Change>,
+}
+
+/// What `reschedule` is about to do, and whether it's been OK'd.
+pub struct Plan {
+    changes: Vec<Change>,
+    approved: bool,
+}
+
+impl Plan {
+    /// Move the items, if the plan was approved, and say what happened.
+    pub fn apply(self, conn: &Connection, format: Format) -> Result<()> {
+        let Plan {
+            mut changes,
+            approved,
+        } = self;
+
+        if approved {
+            // only `next` changes, so load each item fresh instead of
+            // saving over anything else that changed since we planned.
+            for change in &mut changes {
+                let mut item = Item::get(change.item.id, conn)
+                    .with_context(|| format!("couldn't load item with ID {}", change.item.id))?;
+                item.next = change.item.next;
+                item.save(conn)
+                    .with_context(|| format!("couldn't save item with ID {}", item.id))?;
+
+                change.item = item;
+            }
+        }
+
+        match format {
+            Format::Human => {
+                if changes.is_empty() {
+                    println!("No items matched, so there's nothing to move");
+                } else if approved {
+                    println!("Moved {} items", changes.len());
+                } else {
+                    print!("{}", preview(&changes));
+                    println!("Nothing has been moved yet. Pass --yes to make these changes");
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&Output {
+                    applied: approved,
+                    changes
+                })
+                .context("couldn't convert changes to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+impl Command {
+    /// Work out which items would move where, then ask whether to go ahead
+    /// (unless `--yes` already did.) Answering can take a while, so this
+    /// runs before `run` takes the write lock.
+    pub fn plan(&self, conn: &Connection, format: Format) -> Result<Plan> {
+        let changes = self.changes(conn)?;
+
+        let approved = if self.yes {
+            true
+        } else if format == Format::Human && std::io::stdin().is_tty() && !changes.is_empty() {
+            confirm(&changes, std::io::stdin().lock(), std::io::stdout())?
+        } else {
+            false
+        };
+
+        Ok(Plan { changes, approved })
+    }
+
+    fn changes(&self, conn: &Connection) -> Result<Vec<Change>> {
+        let tag_id = match &self.tag {
+            Some(name) => Some(
+                Tag::get_by_name(conn, name)
+                    .with_context(|| format!("couldn't find a tag named \"{name}\""))?
+                    .id,
+            ),
+            None => None,
+        };
+
+        let mut items = Vec::new();
+        for item in Item::all(conn).context("couldn't get items from the database")? {
+            if item.archived || (tag_id.is_some() && item.tag_id != tag_id) {
+                continue;
+            }
+
+            let mut matches = true;
+            for filter in &self.filters {
+                matches = matches && filter.matches(&item, conn)?;
+            }
+
+            if matches {
+                items.push(item);
+            }
+        }
+
+        let before: Vec<(u64, Date)> = items.iter().map(|item| (item.id, item.next)).collect();
+
+        if let Some(over) = self.spread_over {
+            reschedule::spread(&mut items, Date::today(), over);
+        }
+        for item in &mut items {
+            if let Some(shift) = self.shift {
+                item.next = item.next + shift;
+            }
+            if let Some(to) = self.to {
+                item.next = to;
+            }
+        }
+
+        Ok(items
+            .into_iter()
+            .map(|item| {
+                let from = before
+                    .iter()
+                    .find(|(id, _)| *id == item.id)
+                    .map_or(item.next, |(_, next)| *next);
+
+                Change { from, item }
+            })
+            .collect())
+    }
+}
+
+fn preview(changes: &[Change]) -> String {
+    let mut out = String::new();
+    for change in changes {
+        // writing to a String can't fail
+        let _ = writeln!(
+            out,
+            "{}: {} ({} -> {})",
+            change.item.id, change.item.text, change.from, change.item.next
+        );
+    }
+
+    out
+}
+
+fn confirm(changes: &[Change], mut input: impl BufRead, mut out: impl Write) -> Result<bool> {
+    write!(out, "{}", preview(changes))?;
+    write!(out, "\nMove these {} items? [y/N] ", changes.len())?;
+    out.flush()?;
+
+    let mut answer = String::new();
+    input
+        .read_line(&mut answer)
+        .context("couldn't read an answer")?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn.execute("INSERT INTO tags (name) VALUES ('reading')", [])
+            .unwrap();
+        for (tag_id, cadence, next) in [
+            (Some(1), 7, Date::ymd(2022, 1, 5)),
+            (Some(1), 30, Date::ymd(2022, 1, 1)),
+            (None, 7, Date::ymd(2022, 1, 3)),
+        ] {
+            conn.execute(
+                "INSERT INTO items (text, cadence, next, tag_id, integral) VALUES ('test', ?, ?, ?, 1.5)",
+                params![cadence, next, tag_id],
+            )
+            .unwrap();
+        }
+
+        conn
+    }
+
+    fn run(conn: &Connection, args: &[&str]) {
+        let command = Command::try_parse_from(std::iter::once(&"reschedule").chain(args)).unwrap();
+        let plan = command.plan(conn, Format::Json).unwrap();
+
+        plan.apply(conn, Format::Json).unwrap();
+    }
+
+    fn nexts(conn: &Connection) -> Vec<Date> {
+        Item::all(conn).unwrap().map(|item| item.next).collect()
+    }
+
+    #[test]
+    fn requires_a_change() {
+        assert!(Command::try_parse_from(["reschedule", "--tag", "reading"]).is_err());
+        assert!(
+            Command::try_parse_from(["reschedule", "--shift", "1d", "--to", "2022-01-01"]).is_err()
+        );
+    }
+
+    #[test]
+    fn shifts_tagged_items() {
+        let conn = conn();
+        run(&conn, &["--tag", "reading", "--shift", "3d", "--yes"]);
+
+        assert_eq!(
+            vec![
+                Date::ymd(2022, 1, 8),
+                Date::ymd(2022, 1, 4),
+                Date::ymd(2022, 1, 3)
+            ],
+            nexts(&conn)
+        );
+    }
+
+    #[test]
+    fn filters_and_moves_to_a_date() {
+        let conn = conn();
+        run(
+            &conn,
+            &["--where", "cadence<2w", "--to", "2022-02-01", "--yes"],
+        );
+
+        assert_eq!(
+            vec![
+                Date::ymd(2022, 2, 1),
+                Date::ymd(2022, 1, 1),
+                Date::ymd(2022, 2, 1)
+            ],
+            nexts(&conn)
+        );
+    }
+
+    #[test]
+    fn spreads_from_today() {
+        let conn = conn();
+        run(&conn, &["--tag", "reading", "--spread-over", "4d", "--yes"]);
+
+        let today = Date::today();
+        assert_eq!(
+            vec![today + Cadence::days(2), today, Date::ymd(2022, 1, 3)],
+            nexts(&conn)
+        );
+    }
+
+    #[test]
+    fn leaves_schedule_state_alone() {
+        let conn = conn();
+        let before: Vec<Item> = Item::all(&conn).unwrap().collect();
+
+        run(&conn, &["--shift", "1w", "--yes"]);
+
+        for (before, after) in before.iter().zip(Item::all(&conn).unwrap()) {
+            assert_eq!(before.cadence, after.cadence);
+            assert_eq!(before.pid, after.pid);
+        }
+    }
+
+    #[test]
+    fn previews_without_yes() {
+        let conn = conn();
+        let before = nexts(&conn);
+
+        run(&conn, &["--shift", "1w"]);
+
+        assert_eq!(before, nexts(&conn));
+    }
+
+    #[test]
+    fn confirms() {
+        let conn = conn();
+        let command = Command::try_parse_from(["reschedule", "--shift", "1d"]).unwrap();
+        let changes = command.changes(&conn).unwrap();
+
+        assert!(confirm(&changes, "y\n".as_bytes(), Vec::new()).unwrap());
+        assert!(!confirm(&changes, "\n".as_bytes(), Vec::new()).unwrap());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::db;
+use crate::format::Format;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The backup to restore (for example, one made by `tempo backup`.)
+    /// This replaces everything currently in the store!
+    path: PathBuf,
+}
+
+impl Command {
+    pub fn run(&self, conn: &mut Connection, format: Format) -> Result<()> {
+        let version = self.validate()?;
+
+        conn.restore(DatabaseName::Main, &self.path, None::<fn(Progress)>)
+            .with_context(|| format!("could not restore from {}", self.path.display()))?;
+
+        // backups made by older versions of Tempo need to be brought up to
+        // date before we can use them.
+        db::migrations::runner()
+            .run(conn)
+            .context("couldn't migrate the restored database")?;
+
+        match format {
+            Format::Human => println!(
+                "Restored the database from {} (schema version {})",
+                self.path.display(),
+                version
+            ),
+            Format::Json => println!("{}", serde_json::to_string(&true)?),
+        }
+
+        Ok(())
+    }
+
+    /// Make sure the backup is something we can actually use before we
+    /// overwrite the store with it. Returns the backup's schema version.
+    fn validate(&self) -> Result<u32> {
+        if !self.path.is_file() {
+            bail!("{} does not exist or is not a file", self.path.display())
+        }
+
+        let backup = Connection::open_with_flags(&self.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("could not open {}", self.path.display()))?;
+
+        let Some(version) = db::schema_version(&backup)
+            .with_context(|| format!("{} is not a SQLite database", self.path.display()))?
+        else {
+            bail!(
+                "{} doesn't look like a Tempo database (it has no migration history)",
+                self.path.display()
+            )
+        };
+
+        let latest = db::latest_version();
+        if version > latest {
+            bail!(
+                "{} was made by a newer version of Tempo (schema version {}, but I only know up to {}.) Upgrade Tempo and try again",
+                self.path.display(),
+                version,
+                latest,
+            )
+        }
+
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scratch_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "tempo-test-{}-restore-{}.sqlite3",
+            std::process::id(),
+            name
+        ))
+    }
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn
+    }
+
+    #[test]
+    fn restores_backup() {
+        let path = scratch_path("valid");
+        let backup = conn();
+        backup
+            .execute(
+                "INSERT INTO items (text, next) VALUES (\"from backup\", \"2022-01-01T00:00:00+00:00\")",
+                [],
+            )
+            .unwrap();
+        backup.backup(DatabaseName::Main, &path, None).unwrap();
+
+        let mut conn = conn();
+        let command = Command::try_parse_from(["restore", path.to_str().unwrap()]).unwrap();
+        command.run(&mut conn, Format::Human).unwrap();
+
+        assert_eq!(
+            "from backup".to_string(),
+            conn.query_row("SELECT text FROM items", [], |row| row.get::<_, String>(0))
+                .unwrap()
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refuses_non_tempo_database() {
+        let path = scratch_path("unmigrated");
+        Connection::open(&path)
+            .unwrap()
+            .execute("CREATE TABLE other (id INTEGER)", [])
+            .unwrap();
+
+        let mut conn = conn();
+        let command = Command::try_parse_from(["restore", path.to_str().unwrap()]).unwrap();
+        assert!(command.run(&mut conn, Format::Human).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refuses_newer_schema() {
+        let path = scratch_path("newer");
+        let backup = conn();
+        backup
+            .execute(
+                "UPDATE refinery_schema_history SET version = ? WHERE version = ?",
+                [db::latest_version() + 1, db::latest_version()],
+            )
+            .unwrap();
+        backup.backup(DatabaseName::Main, &path, None).unwrap();
+
+        let mut conn = conn();
+        let command = Command::try_parse_from(["restore", path.to_str().unwrap()]).unwrap();
+        assert!(command.run(&mut conn, Format::Human).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refuses_missing_file() {
+        let mut conn = conn();
+        let command =
+            Command::try_parse_from(["restore", scratch_path("missing").to_str().unwrap()])
+                .unwrap();
+
+        assert!(command.run(&mut conn, Format::Human).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::format::Format;
+use crate::retire::{Candidate, Policy, Reason};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use crossterm::tty::IsTty;
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[clap(flatten)]
+    policy: Policy,
+
+    /// Go through the candidates and choose which ones to archive
+    #[clap(long, short)]
+    review: bool,
+
+    /// Archive every candidate without asking
+    #[clap(long, short, conflicts_with = "review")]
+    yes: bool,
+}
+
+impl Command {
+    /// With `--review`, show the candidates and ask which to archive. This
+    /// runs before `run` takes the write lock, so the rest of Tempo doesn't
+    /// have to wait on us while we wait on an answer. Returns `None` when
+    /// there's nothing to ask about.
+    pub fn choose(&self, conn: &Connection, format: Format) -> Result<Option<HashSet<u64>>> {
+        if !self.review {
+            return Ok(None);
+        }
+
+        let candidates = self.policy.candidates(conn)?;
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        if format == Format::Json || !std::io::stdin().is_tty() {
+            bail!("--review needs to ask you which items to archive, so it only works in a terminal. Use --yes to archive all of them")
+        }
+
+        let chosen = review(candidates, std::io::stdin().lock(), std::io::stdout())?;
+
+        Ok(Some(
+            chosen.iter().map(|candidate| candidate.item.id).collect(),
+        ))
+    }
+
+    /// Archive the candidates chosen in `choose` (or all of them, with
+    /// `--yes`), or list them if there's nothing to archive yet.
+    pub fn run(
+        &self,
+        conn: &Connection,
+        format: Format,
+        chosen: Option<&HashSet<u64>>,
+    ) -> Result<()> {
+        let candidates = self.policy.candidates(conn)?;
+
+        let chosen: Vec<Candidate> = if self.yes {
+            candidates
+        } else if let Some(ids) = chosen {
+            candidates
+                .into_iter()
+                .filter(|candidate| ids.contains(&candidate.item.id))
+                .collect()
+        } else {
+            match format {
+                Format::Human => {
+                    if candidates.is_empty() {
+                        println!("Nothing to retire right now!");
+                    }
+                    for candidate in &candidates {
+                        println!("{}", describe(candidate));
+                    }
+                }
+                Format::Json => println!(
+                    "{}",
+                    serde_json::to_string(&candidates)
+                        .context("couldn't convert candidates to JSON")?
+                ),
+            }
+
+            return Ok(());
+        };
+
+        for candidate in &chosen {
+            let mut item = candidate.item.clone();
+            item.archived = true;
+            item.save(conn)
+                .with_context(|| format!("couldn't save item with ID {}", item.id))?;
+        }
+
+        match format {
+            Format::Human => println!(
+                "Archived {} items. Use `tempo unarchive` to bring any of them back",
+                chosen.len()
+            ),
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&chosen).context("couldn't convert items to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+fn describe(candidate: &Candidate) -> String {
+    let why = match &candidate.reason {
+        Reason::MuchLater { streak } => format!("\"much-later\" {streak} times in a row"),
+        Reason::LongCadence { cadence } => format!("cadence has grown to {cadence}"),
+    };
+
+    format!("{}: {} ({})", candidate.item.id, candidate.item.text, why)
+}
+
+/// Show the candidates and ask which to archive: all of them, none of
+/// them, or some IDs.
+fn review(
+    candidates: Vec<Candidate>,
+    mut input: impl BufRead,
+    mut out: impl Write,
+) -> Result<Vec<Candidate>> {
+    for candidate in &candidates {
+        writeln!(out, "{}", describe(candidate))?;
+    }
+    writeln!(out)?;
+
+    loop {
+        write!(
+            out,
+            "Archive which? [a]ll, [n]one, or IDs separated by spaces: "
+        )?;
+        out.flush()?;
+
+        let mut answer = String::new();
+        if input
+            .read_line(&mut answer)
+            .context("couldn't read an answer")?
+            == 0
+        {
+            return Ok(Vec::new());
+        }
+
+        match answer.trim().to_lowercase().as_str() {
+            "a" | "all" => return Ok(candidates),
+            "" | "n" | "none" => return Ok(Vec::new()),
+            ids => match ids
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<HashSet<u64>, _>>()
+            {
+                Ok(ids)
+                    if ids
+                        .iter()
+                        .all(|id| candidates.iter().any(|c| c.item.id == *id)) =>
+                {
+                    return Ok(candidates
+                        .into_iter()
+                        .filter(|candidate| ids.contains(&candidate.item.id))
+                        .collect())
+                }
+                _ => writeln!(
+                    out,
+                    "Sorry, I need \"all\", \"none\", or some of the IDs above"
+                )?,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cadence::Cadence;
+    use crate::date::Date;
+    use crate::item::Item;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        for cadence in [Cadence::days(1), Cadence::years(3), Cadence::years(4)] {
+            conn.execute(
+                "INSERT INTO items (text, cadence, next) VALUES ('test', ?, ?)",
+                params![cadence, Date::ymd(2022, 1, 1)],
+            )
+            .unwrap();
+        }
+
+        conn
+    }
+
+    fn ask(answers: &str) -> Vec<u64> {
+        let conn = conn();
+        let candidates =
+            Policy::try_parse_from(["retire", "--after-much-later", "3", "--longer-than", "2y"])
+                .unwrap()
+                .candidates(&conn)
+                .unwrap();
+
+        review(candidates, answers.as_bytes(), Vec::new())
+            .unwrap()
+            .iter()
+            .map(|candidate| candidate.item.id)
+            .collect()
+    }
+
+    #[test]
+    fn review_all() {
+        assert_eq!(vec![2, 3], ask("all\n"));
+    }
+
+    #[test]
+    fn review_none() {
+        assert!(ask("n\n").is_empty());
+        assert!(ask("").is_empty());
+    }
+
+    #[test]
+    fn review_some_ids() {
+        assert_eq!(vec![3], ask("1\n3\n"));
+    }
+
+    #[test]
+    fn yes_archives_everything() {
+        let conn = conn();
+        let command = Command::try_parse_from([
+            "retire",
+            "--after-much-later",
+            "3",
+            "--longer-than",
+            "2y",
+            "--yes",
+        ])
+        .unwrap();
+
+        command.run(&conn, Format::Json, None).unwrap();
+
+        let archived: Vec<bool> = Item::all(&conn)
+            .unwrap()
+            .map(|item| item.archived)
+            .collect();
+        assert_eq!(vec![false, true, true], archived);
+    }
+
+    #[test]
+    fn listing_changes_nothing() {
+        let conn = conn();
+        let command =
+            Command::try_parse_from(["retire", "--after-much-later", "3", "--longer-than", "2y"])
+                .unwrap();
+
+        command.run(&conn, Format::Json, None).unwrap();
+
+        assert!(Item::all(&conn).unwrap().all(|item| !item.archived));
+    }
+}
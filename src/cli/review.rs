@@ -0,0 +1,164 @@
+use crate::format::Format;
+use crate::review::{self, Key};
+use anyhow::{Context, Result};
+use clap::Parser;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal;
+use crossterm::tty::IsTty;
+use rusqlite::Connection;
+use std::io::{Read, Write};
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// Only review up to this many items
+    #[clap(long, short)]
+    limit: Option<usize>,
+
+    /// Only review items with these tags
+    #[clap(long, short)]
+    tag: Option<Vec<String>>,
+}
+
+impl Command {
+    pub fn run(&self, conn: &mut Connection, format: Format) -> Result<()> {
+        let items = super::ready::query(conn, self.tag.as_deref(), self.limit, false)?;
+        if items.is_empty() {
+            match format {
+                Format::Human => println!("Nothing to review right now!"),
+                Format::Json => println!("[]"),
+            }
+
+            return Ok(());
+        }
+
+        // with JSON output, the session itself goes to stderr so that
+        // stdout only has the changes.
+        let mut screen: Box<dyn Write> = match format {
+            Format::Human => Box::new(std::io::stdout()),
+            Format::Json => Box::new(std::io::stderr()),
+        };
+
+        // when stdin isn't a terminal (for example, when keys are piped in
+        // from a script) we read keys from it one character at a time
+        // instead of putting the terminal into raw mode.
+        let changes = if std::io::stdin().is_tty() {
+            let _raw = RawMode::enable()?;
+            review::run(conn, items, &mut TerminalKeys, &mut screen, &mut open)?
+        } else {
+            let mut script = String::new();
+            std::io::stdin()
+                .read_to_string(&mut script)
+                .context("could not read keys from stdin")?;
+
+            let mut keys = scripted_keys(&script);
+            review::run(conn, items, &mut keys, &mut screen, &mut open)?
+        };
+
+        if format == Format::Json {
+            println!(
+                "{}",
+                serde_json::to_string(&changes).context("could not convert changes to JSON")?
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Turn a script into key presses. A line ending is one press of Enter
+/// whether it's "\n", "\r\n", or "\r", so scripts written on Windows
+/// work the same as anywhere else.
+fn scripted_keys(script: &str) -> impl Iterator<Item = Key> + '_ {
+    let mut chars = script.chars().peekable();
+
+    core::iter::from_fn(move || {
+        let key = match chars.next()? {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                Key::Enter
+            }
+            '\n' => Key::Enter,
+            '\u{1b}' => Key::Escape,
+            '\u{7f}' | '\u{8}' => Key::Backspace,
+            c => Key::Char(c),
+        };
+
+        Some(key)
+    })
+}
+
+/// Raw mode lets us read single key presses, but we have to put the
+/// terminal back the way we found it when we're done (even if something
+/// goes wrong.)
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> Result<RawMode> {
+        terminal::enable_raw_mode().context("could not put the terminal into raw mode")?;
+
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Err(err) = terminal::disable_raw_mode() {
+            log::warn!("could not restore the terminal: {err}");
+        }
+    }
+}
+
+struct TerminalKeys;
+
+impl Iterator for TerminalKeys {
+    type Item = Key;
+
+    fn next(&mut self) -> Option<Key> {
+        loop {
+            let Event::Key(KeyEvent { code, modifiers }) = event::read().ok()? else {
+                continue;
+            };
+
+            return Some(match code {
+                KeyCode::Char('c' | 'd') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    Key::Escape
+                }
+                KeyCode::Char(c) => Key::Char(c),
+                KeyCode::Enter => Key::Enter,
+                KeyCode::Backspace => Key::Backspace,
+                KeyCode::Esc => Key::Escape,
+                _ => continue,
+            });
+        }
+    }
+}
+
+fn open(url: &str) -> Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    std::process::Command::new(opener)
+        .arg(url)
+        .spawn()
+        .with_context(|| format!("could not run {opener}"))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_line_ending_is_one_enter() {
+        for script in ["f\nq", "f\r\nq", "f\rq"] {
+            assert_eq!(
+                vec![Key::Char('f'), Key::Enter, Key::Char('q')],
+                scripted_keys(script).collect::<Vec<_>>(),
+            );
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use crate::rpc;
+use anyhow::Result;
+use clap::Parser;
+use rusqlite::Connection;
+
+#[derive(Debug, Parser)]
+pub struct Command {}
+
+impl Command {
+    #[allow(clippy::unused_self)] // same signature as every other command
+    pub fn run(&self, conn: &mut Connection) -> Result<()> {
+        let stdin = std::io::stdin();
+        let stdout = std::io::stdout();
+
+        rpc::serve(conn, stdin.lock(), stdout.lock())
+    }
+}
@@ -0,0 +1,65 @@
+use crate::format::Format;
+use crate::server::Server;
+use anyhow::{Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+use std::net::SocketAddr;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The address to listen on. This serves your items to anyone who can
+    /// reach it, so think twice before binding to something other than
+    /// localhost.
+    #[clap(long, default_value = "127.0.0.1:7878")]
+    bind: SocketAddr,
+
+    /// Allow adding, editing, finishing, snoozing, and deleting items over
+    /// HTTP for anyone who sends this token (as `Authorization: Bearer
+    /// <token>`.) Without a token, the server is read-only.
+    #[clap(long, env = "TEMPO_SERVE_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Output {
+    address: SocketAddr,
+    read_only: bool,
+}
+
+impl Command {
+    pub fn run(&self, conn: &mut Connection, format: Format) -> Result<()> {
+        let server = Server::bind(self.bind, self.token.clone())?;
+        let address = server
+            .address()
+            .context("could not get the address we're listening on")?;
+
+        match format {
+            Format::Human => {
+                println!("Listening on http://{address}. Available feeds:");
+                println!();
+                println!("    /ready.json     items that are due (?tag=...&limit=...&include_archived=true)");
+                println!("    /items.json     all items (?tag=...&include_archived=true)");
+                println!("    /calendar.ics   upcoming items (?tag=...&horizon=...)");
+                println!();
+
+                if self.token.is_some() {
+                    println!("Changes are allowed with the token (POST /items, PATCH and DELETE /items/ID, POST /items/ID/finish and /items/ID/snooze)");
+                } else {
+                    println!("This server is read-only. Pass --token to allow changes");
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&Output {
+                    address,
+                    read_only: self.token.is_none(),
+                })
+                .context("could not convert the address to JSON")?
+            ),
+        }
+
+        server.run(conn);
+
+        Ok(())
+    }
+}
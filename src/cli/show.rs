@@ -0,0 +1,215 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::format::Format;
+use crate::item::{Bump, Item};
+use crate::status::Status;
+use crate::tag::Tag;
+use anyhow::{Context, Result};
+use clap::{ArgEnum, Parser};
+use rusqlite::Connection;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// ID of the item to show
+    id: u64,
+
+    /// Also show what each bump would do to the schedule if you finished
+    /// the item today. Nothing gets saved.
+    #[clap(long)]
+    what_if: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Output {
+    item: Item,
+    tag: Option<String>,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    what_if: Option<Vec<Projection>>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Projection {
+    bump: &'static str,
+    adjustment: Cadence,
+    cadence: Cadence,
+    next: Date,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        let item = Item::get(self.id, conn)
+            .with_context(|| format!("couldn't load item with ID {}", self.id))?;
+
+        let tag = match item.tag_id {
+            Some(tag_id) => Some(Tag::get(conn, tag_id)?.name),
+            None => None,
+        };
+
+        let what_if = if self.what_if {
+            Some(project(&item))
+        } else {
+            None
+        };
+
+        let status = Status::of(&item, conn)?;
+        let output = Output {
+            item,
+            tag,
+            status,
+            what_if,
+        };
+
+        match format {
+            Format::Human => print!("{}", human(&output)),
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&output).context("couldn't convert item to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+/// Bump a copy of the item in every possible way, finishing today.
+fn project(item: &Item) -> Vec<Projection> {
+    let today = Date::today();
+
+    Bump::value_variants()
+        .iter()
+        .map(|bump| {
+            let mut copy = item.clone();
+            let adjustment = copy.bump_cadence_for_finish(bump);
+
+            Projection {
+                bump: bump
+                    .to_possible_value()
+                    .map_or("", |value| value.get_name()),
+                adjustment,
+                cadence: copy.cadence,
+                next: today + copy.cadence,
+            }
+        })
+        .collect()
+}
+
+fn human(output: &Output) -> String {
+    let item = &output.item;
+    let mut lines = vec![
+        format!("{}: {}", item.id, item.text),
+        format!(
+            "  tag:        {}",
+            output.tag.as_deref().unwrap_or("(none)")
+        ),
+        format!("  cadence:    {}", item.cadence),
+        format!("  next:       {}", item.next),
+        format!("  status:     {}", output.status),
+    ];
+
+    if item.learning_steps > 0 {
+        lines.push(format!("  graduating: in {} step(s)", item.learning_steps));
+    }
+
+    if let Some(until) = item.paused_until {
+        lines.push(format!("  paused:     until {until}"));
+    }
+    if item.archived {
+        lines.push("  archived:   yes".into());
+    }
+
+    lines.push(format!(
+        "  pid:        integral {:.2}, last error {:.2}",
+        item.pid.integral, item.pid.last_error
+    ));
+
+    if let Some(projections) = &output.what_if {
+        lines.push(String::new());
+        lines.push("If you finished it today:".into());
+        for projection in projections {
+            lines.push(format!(
+                "  {:<12} cadence {} ({:+} days), next on {}",
+                projection.bump, projection.cadence, projection.adjustment.days, projection.next
+            ));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::runner().run(&mut conn).unwrap();
+
+        conn.execute("INSERT INTO tags (name) VALUES ('chores')", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO items (text, tag_id, cadence, next) VALUES (?, 1, ?, ?)",
+            params!["test", Cadence::days(7), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn projects_every_bump() {
+        let item = Item::get(1, &setup()).unwrap();
+        let projections = project(&item);
+
+        assert_eq!(
+            vec![
+                "much-earlier",
+                "earlier",
+                "just-right",
+                "later",
+                "much-later"
+            ],
+            projections.iter().map(|p| p.bump).collect::<Vec<_>>()
+        );
+
+        let just_right = &projections[2];
+        assert_eq!(Cadence::days(7), just_right.cadence);
+        assert_eq!(Date::today() + Cadence::days(7), just_right.next);
+
+        assert!(projections[0].cadence < just_right.cadence);
+        assert!(projections[4].cadence > just_right.cadence);
+    }
+
+    #[test]
+    fn what_if_does_not_save() {
+        let conn = setup();
+        let before = Item::get(1, &conn).unwrap();
+
+        let command = Command::try_parse_from(["show", "1", "--what-if"]).unwrap();
+        command.run(&conn, Format::Json).unwrap();
+
+        assert_eq!(before, Item::get(1, &conn).unwrap());
+    }
+
+    #[test]
+    fn human_output_includes_state() {
+        let conn = setup();
+        let mut item = Item::get(1, &conn).unwrap();
+        item.learning_steps = 2;
+        let output = Output {
+            item,
+            tag: Some("chores".into()),
+            status: Status::Learning,
+            what_if: None,
+        };
+
+        let text = human(&output);
+        assert!(text.contains("tag:        chores"));
+        assert!(text.contains("cadence:    1w"));
+        assert!(text.contains("status:     learning"));
+        assert!(text.contains("graduating: in 2 step(s)"));
+        assert!(text.contains("integral 0.00"));
+        assert!(!text.contains("If you finished"));
+    }
+}
@@ -0,0 +1,392 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::format::Format;
+use crate::history::Finish;
+use crate::item::{Bump, Item};
+use crate::status::Status;
+use crate::tag::Tag;
+use crate::vacation::{self, Vacation};
+use anyhow::{Context, Result};
+use clap::{ArgEnum, Parser};
+use rusqlite::Connection;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// How far back to look when counting finishes. Supports the same
+    /// units as `add --cadence`.
+    #[clap(long, short, default_value = "30d")]
+    since: Cadence,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Stats {
+    tags: Vec<TagStats>,
+    overdue: Overdue,
+    unsettled: Vec<Unsettled>,
+    finishes: Finishes,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct TagStats {
+    tag: Option<String>,
+    items: usize,
+    median_cadence: Cadence,
+    min_cadence: Cadence,
+    max_cadence: Cadence,
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+struct Overdue {
+    items: usize,
+    total_days: i64,
+    max_days: i64,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Unsettled {
+    id: u64,
+    text: String,
+    integral: f64,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct Finishes {
+    #[serde(with = "crate::date::ymd")]
+    since: Date,
+    total: usize,
+    bumps: BTreeMap<&'static str, usize>,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        let tag_names: HashMap<u64, String> = Tag::all(conn)
+            .context("couldn't get tags")?
+            .map(|tag| (tag.id, tag.name))
+            .collect();
+
+        let items: Vec<Item> = Item::all(conn)
+            .context("couldn't get items from the database")?
+            .filter(|item| !item.archived)
+            .collect();
+
+        let vacations: Vec<Vacation> = Vacation::all(conn)?.collect();
+
+        let since = Date::today() - self.since;
+        let stats = Stats {
+            tags: tags(&items, &tag_names),
+            overdue: overdue(&items, &vacations, Date::today()),
+            unsettled: unsettled(&items, conn)?,
+            finishes: finishes(Finish::since(conn, since)?, since),
+        };
+
+        match format {
+            Format::Human => print!("{}", human(&stats)),
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&stats).context("couldn't convert stats to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+fn tags(items: &[Item], tag_names: &HashMap<u64, String>) -> Vec<TagStats> {
+    let mut cadences: BTreeMap<Option<&String>, Vec<Cadence>> = BTreeMap::new();
+    for item in items {
+        cadences
+            .entry(item.tag_id.and_then(|id| tag_names.get(&id)))
+            .or_default()
+            .push(item.cadence);
+    }
+
+    cadences
+        .into_iter()
+        .map(|(tag, mut cadences)| {
+            cadences.sort_by_key(|cadence| cadence.days);
+
+            TagStats {
+                tag: tag.cloned(),
+                items: cadences.len(),
+                median_cadence: Cadence::median(cadences.iter().copied()).unwrap_or_default(),
+                min_cadence: cadences[0],
+                max_cadence: cadences[cadences.len() - 1],
+            }
+        })
+        .collect()
+}
+
+/// How overdue items are, not counting days spent on vacation (finishing
+/// late because you were away isn't really late.)
+fn overdue(items: &[Item], vacations: &[Vacation], today: Date) -> Overdue {
+    items
+        .iter()
+        .filter(|item| item.paused_until.is_none_or(|until| until <= today))
+        .map(|item| vacation::days_late(vacations, item, today))
+        .filter(|days| *days > 0)
+        .fold(Overdue::default(), |overdue, days| Overdue {
+            items: overdue.items + 1,
+            total_days: overdue.total_days + days,
+            max_days: overdue.max_days.max(days),
+        })
+}
+
+/// Items that haven't settled yet, by the same rules `all` and `show` use,
+/// with the ones furthest off first.
+fn unsettled(items: &[Item], conn: &Connection) -> Result<Vec<Unsettled>> {
+    let mut unsettled = Vec::new();
+    for item in items {
+        if Status::of(item, conn)? == Status::Learning {
+            unsettled.push(Unsettled {
+                id: item.id,
+                text: item.text.clone(),
+                integral: item.pid.integral,
+            });
+        }
+    }
+
+    unsettled.sort_by(|a, b| b.integral.abs().total_cmp(&a.integral.abs()));
+    Ok(unsettled)
+}
+
+fn finishes(history: impl Iterator<Item = Finish>, since: Date) -> Finishes {
+    let mut bumps: BTreeMap<&'static str, usize> = Bump::value_variants()
+        .iter()
+        .map(|bump| (bump.name(), 0))
+        .collect();
+
+    let mut total = 0;
+    for finish in history {
+        total += 1;
+        *bumps.entry(finish.bump.name()).or_default() += 1;
+    }
+
+    Finishes {
+        since,
+        total,
+        bumps,
+    }
+}
+
+fn human(stats: &Stats) -> String {
+    let mut out = String::new();
+
+    // writing to a String can't fail
+    let _ = writeln!(
+        out,
+        "{:<20} {:>5} {:>7} {:>7} {:>7}",
+        "Tag", "Items", "Median", "Min", "Max"
+    );
+    for tag in &stats.tags {
+        let _ = writeln!(
+            out,
+            "{:<20} {:>5} {:>7} {:>7} {:>7}",
+            tag.tag.as_deref().unwrap_or("(no tag)"),
+            tag.items,
+            tag.median_cadence.to_string(),
+            tag.min_cadence.to_string(),
+            tag.max_cadence.to_string(),
+        );
+    }
+
+    let overdue = &stats.overdue;
+    if overdue.items == 0 {
+        let _ = writeln!(out, "\nNothing is overdue.");
+    } else {
+        let _ = writeln!(
+            out,
+            "\n{} overdue, by {} days in total (at most {})",
+            overdue.items, overdue.total_days, overdue.max_days
+        );
+    }
+
+    if !stats.unsettled.is_empty() {
+        let _ = writeln!(out, "\nStill finding their schedule:");
+        for item in &stats.unsettled {
+            let _ = writeln!(
+                out,
+                "  {}: {} (integral {:.2})",
+                item.id, item.text, item.integral
+            );
+        }
+    }
+
+    let finishes = &stats.finishes;
+    let _ = writeln!(
+        out,
+        "\nFinished {} since {}",
+        finishes.total, finishes.since
+    );
+    if finishes.total > 0 {
+        for bump in Bump::value_variants() {
+            let count = finishes.bumps.get(bump.name()).unwrap_or(&0);
+            let _ = writeln!(out, "  {:<12} {count:>5}", bump.name());
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pid::Pid;
+
+    fn item(id: u64, tag_id: Option<u64>, cadence: i64, next: Date) -> Item {
+        Item {
+            id,
+            text: format!("item {id}"),
+            tag_id,
+            cadence: Cadence::days(cadence),
+            next,
+            pid: Pid::default(),
+            archived: false,
+            paused_until: None,
+            learning_steps: 0,
+        }
+    }
+
+    #[test]
+    fn groups_by_tag() {
+        let today = Date::ymd(2022, 1, 10);
+        let items = vec![
+            item(1, Some(1), 7, today),
+            item(2, Some(1), 1, today),
+            item(3, Some(1), 14, today),
+            item(4, None, 3, today),
+        ];
+        let tag_names = HashMap::from([(1, "home".to_string())]);
+
+        assert_eq!(
+            vec![
+                TagStats {
+                    tag: None,
+                    items: 1,
+                    median_cadence: Cadence::days(3),
+                    min_cadence: Cadence::days(3),
+                    max_cadence: Cadence::days(3),
+                },
+                TagStats {
+                    tag: Some("home".into()),
+                    items: 3,
+                    median_cadence: Cadence::days(7),
+                    min_cadence: Cadence::days(1),
+                    max_cadence: Cadence::days(14),
+                },
+            ],
+            tags(&items, &tag_names)
+        );
+    }
+
+    #[test]
+    fn counts_overdue_days() {
+        let today = Date::ymd(2022, 1, 10);
+        let mut paused = item(3, None, 1, Date::ymd(2022, 1, 1));
+        paused.paused_until = Some(Date::ymd(2022, 2, 1));
+
+        let items = vec![
+            item(1, None, 1, Date::ymd(2022, 1, 7)),
+            item(2, None, 1, Date::ymd(2022, 1, 9)),
+            paused,
+            item(4, None, 1, Date::ymd(2022, 1, 12)),
+        ];
+
+        assert_eq!(
+            Overdue {
+                items: 2,
+                total_days: 4,
+                max_days: 3,
+            },
+            overdue(&items, &[], today)
+        );
+    }
+
+    #[test]
+    fn vacation_days_are_not_overdue() {
+        let today = Date::ymd(2022, 1, 10);
+        let items = vec![item(1, None, 1, Date::ymd(2022, 1, 1))];
+        let vacations = vec![Vacation {
+            id: 1,
+            tag_id: None,
+            starts: Date::ymd(2021, 12, 20),
+            ends: Date::ymd(2022, 1, 7),
+        }];
+
+        assert_eq!(
+            Overdue {
+                items: 1,
+                total_days: 3,
+                max_days: 3,
+            },
+            overdue(&items, &vacations, today)
+        );
+    }
+
+    #[test]
+    fn lists_unsettled_items_by_size() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::runner().run(&mut conn).unwrap();
+
+        let today = Date::ymd(2022, 1, 10);
+        let mut items = vec![
+            item(1, None, 1, today),
+            item(2, None, 1, today),
+            item(3, None, 1, today),
+            item(4, None, 1, today),
+        ];
+        items[0].pid.integral = 2.5;
+        items[2].pid.integral = -4.0;
+        items[3].pid.integral = 0.5;
+
+        // 2 has settled; 4 is close to zero too, but hasn't had a streak of
+        // "just right" yet, so `all` and `show` would still call it learning
+        for item in &items {
+            conn.execute(
+                "INSERT INTO items (id, text, next) VALUES (?, ?, ?)",
+                rusqlite::params![item.id, item.text, item.next],
+            )
+            .unwrap();
+        }
+        for _ in 0..3 {
+            Finish::record(&conn, &items[1], &Bump::JustRight, today).unwrap();
+        }
+        assert_eq!(Status::Settled, Status::of(&items[1], &conn).unwrap());
+
+        let ids: Vec<u64> = unsettled(&items, &conn)
+            .unwrap()
+            .iter()
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(vec![3, 1, 4], ids);
+    }
+
+    #[test]
+    fn breaks_down_bumps() {
+        let since = Date::ymd(2022, 1, 1);
+        let finish = |id, bump| Finish {
+            id,
+            item_id: 1,
+            bump,
+            due: since,
+            finished_at: since,
+            cadence: Cadence::days(1),
+        };
+
+        let summary = finishes(
+            vec![
+                finish(1, Bump::Later),
+                finish(2, Bump::Later),
+                finish(3, Bump::Earlier),
+            ]
+            .into_iter(),
+            since,
+        );
+
+        assert_eq!(3, summary.total);
+        assert_eq!(Some(&2), summary.bumps.get("later"));
+        assert_eq!(Some(&1), summary.bumps.get("earlier"));
+        assert_eq!(Some(&0), summary.bumps.get("much-later"));
+    }
+}
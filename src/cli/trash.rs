@@ -0,0 +1,179 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::format::Format;
+use crate::trash::Trashed;
+use anyhow::{Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[clap(subcommand)]
+    command: Subcommand,
+}
+
+#[derive(Debug, Parser)]
+enum Subcommand {
+    /// Show everything in the trash
+    List,
+
+    /// Move an item out of the trash and back into the store. It'll keep
+    /// its original ID unless another item has taken it since.
+    Restore {
+        /// ID the item had before it was deleted
+        id: u64,
+    },
+
+    /// Permanently delete items in the trash
+    Empty {
+        /// Only delete items that have been in the trash for longer than
+        /// this (e.g. 30d.) Supports the same units as `add --cadence`.
+        #[clap(long)]
+        older_than: Option<Cadence>,
+    },
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        match &self.command {
+            Subcommand::List => Self::list(conn, format),
+            Subcommand::Restore { id } => Self::restore(*id, conn, format),
+            Subcommand::Empty { older_than } => Self::empty(*older_than, conn, format),
+        }
+    }
+
+    fn list(conn: &Connection, format: Format) -> Result<()> {
+        let trashed: Vec<Trashed> = Trashed::all(conn)
+            .context("could not get items in the trash")?
+            .collect();
+
+        match format {
+            Format::Human => {
+                for item in trashed {
+                    println!(
+                        "{}: {} (deleted {})",
+                        item.item_id, item.text, item.deleted_at
+                    );
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&trashed).context("could not dump trashed items to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+
+    fn restore(id: u64, conn: &Connection, format: Format) -> Result<()> {
+        let item = Trashed::latest_for_item(id, conn)?
+            .restore(conn)
+            .with_context(|| format!("could not restore item with ID {id}"))?;
+
+        match format {
+            Format::Human if item.id == id => println!(
+                "Restored \"{}\" with ID {}. Next scheduled on {}",
+                item.text, item.id, item.next
+            ),
+            Format::Human => println!(
+                "Restored \"{}\", but ID {} was taken so it has ID {} now. Next scheduled on {}",
+                item.text, id, item.id, item.next
+            ),
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&item).context("couldn't convert item to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+
+    fn empty(older_than: Option<Cadence>, conn: &Connection, format: Format) -> Result<()> {
+        let removed = Trashed::empty(older_than.map(|cadence| Date::today() - cadence), conn)?;
+
+        match format {
+            Format::Human => println!("Permanently deleted {removed} item(s) from the trash"),
+            Format::Json => println!("{}", serde_json::to_string(&removed)?),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::item::Item;
+    use rusqlite::params;
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::runner().run(&mut conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params!["test", Cadence::days(1), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn delete_then_restore() {
+        let conn = setup();
+        let before = Item::get(1, &conn).unwrap();
+
+        crate::cli::delete::Command::try_parse_from(&["delete", "1"])
+            .unwrap()
+            .run(&conn, Format::Human)
+            .unwrap();
+        assert!(Item::get(1, &conn).is_err());
+
+        Command::try_parse_from(&["trash", "restore", "1"])
+            .unwrap()
+            .run(&conn, Format::Human)
+            .unwrap();
+        assert_eq!(before, Item::get(1, &conn).unwrap());
+    }
+
+    #[test]
+    fn restore_fails_for_unknown_id() {
+        let conn = setup();
+
+        let command = Command::try_parse_from(&["trash", "restore", "1"]).unwrap();
+        assert!(command.run(&conn, Format::Human).is_err());
+    }
+
+    #[test]
+    fn empty_older_than_keeps_recent_items() {
+        let conn = setup();
+        crate::cli::delete::Command::try_parse_from(&["delete", "1"])
+            .unwrap()
+            .run(&conn, Format::Human)
+            .unwrap();
+
+        Command::try_parse_from(&["trash", "empty", "--older-than", "30d"])
+            .unwrap()
+            .run(&conn, Format::Human)
+            .unwrap();
+
+        assert_eq!(1, Trashed::all(&conn).unwrap().count());
+    }
+
+    #[test]
+    fn empty_without_cutoff_removes_everything() {
+        let conn = setup();
+        crate::cli::delete::Command::try_parse_from(&["delete", "1"])
+            .unwrap()
+            .run(&conn, Format::Human)
+            .unwrap();
+
+        Command::try_parse_from(&["trash", "empty"])
+            .unwrap()
+            .run(&conn, Format::Human)
+            .unwrap();
+
+        assert_eq!(0, Trashed::all(&conn).unwrap().count());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::date::Date;
+use crate::format::Format;
+use crate::item::Item;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// ID of the item to bring back
+    id: u64,
+
+    /// Schedule the item one cadence from today instead of keeping the
+    /// next date it had when it was archived (which is probably in the past.)
+    #[clap(long, short)]
+    reschedule: bool,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        let mut item = Item::get(self.id, conn)
+            .with_context(|| format!("couldn't load item with ID {}", self.id))?;
+
+        if !item.archived && item.paused_until.is_none() {
+            bail!("item with ID {} is not archived or paused", self.id)
+        }
+
+        item.archived = false;
+        item.paused_until = None;
+
+        if self.reschedule {
+            item.next = Date::today() + item.cadence;
+        }
+
+        item.save(conn)
+            .with_context(|| format!("couldn't save item with ID {}", self.id))?;
+
+        match format {
+            Format::Human => println!(
+                "Unarchived item with ID {}. Next scheduled on {}",
+                self.id, item.next
+            ),
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&item).context("couldn't convert item to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cadence::Cadence;
+    use rusqlite::params;
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::runner().run(&mut conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO items (text, cadence, next, archived) VALUES (?, ?, ?, ?)",
+            params!["test", Cadence::weeks(1), Date::ymd(2022, 1, 1), true],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn unarchives_item() {
+        let conn = setup();
+        let command = Command::try_parse_from(&["unarchive", "1"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        let item = Item::get(1, &conn).unwrap();
+        assert!(!item.archived);
+        assert_eq!(Date::ymd(2022, 1, 1), item.next);
+    }
+
+    #[test]
+    fn reschedules_relative_to_today() {
+        let conn = setup();
+        let command = Command::try_parse_from(&["unarchive", "1", "--reschedule"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        assert_eq!(
+            Date::today() + Cadence::weeks(1),
+            Item::get(1, &conn).unwrap().next
+        );
+    }
+
+    #[test]
+    fn clears_pause() {
+        let conn = setup();
+        conn.execute(
+            "UPDATE items SET archived = 0, paused_until = ? WHERE id = 1",
+            [Date::today() + Cadence::weeks(1)],
+        )
+        .unwrap();
+
+        let command = Command::try_parse_from(&["unarchive", "1"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        assert_eq!(None, Item::get(1, &conn).unwrap().paused_until);
+    }
+
+    #[test]
+    fn fails_if_not_archived() {
+        let conn = setup();
+        let command = Command::try_parse_from(&["unarchive", "1"]).unwrap();
+        command.run(&conn, Format::Human).unwrap();
+
+        assert!(command.run(&conn, Format::Human).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::format::Format;
+use crate::item::Item;
+use crate::tag::Tag;
+use crate::vacation::{self, Vacation};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+
+#[derive(Debug, Parser)]
+pub struct Command {
+    /// The first day you'll be away. Accepts the same values as
+    /// `add --next`.
+    #[clap(long, parse(try_from_str = super::parse_utc_datetime))]
+    from: Date,
+
+    /// The last day you'll be away
+    #[clap(long, parse(try_from_str = super::parse_utc_datetime))]
+    until: Date,
+
+    /// Only move items with this tag (otherwise, move everything)
+    #[clap(long, short)]
+    tag: Option<String>,
+
+    /// Spread the items that would have come due over this many days after
+    /// you get back, instead of having them all show up at once
+    #[clap(long, short, default_value = "3d")]
+    spread: Cadence,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Output {
+    vacation: Vacation,
+    moved: Vec<Item>,
+}
+
+impl Command {
+    pub fn run(&self, conn: &Connection, format: Format) -> Result<()> {
+        if self.until < self.from {
+            bail!("--until has to be on or after --from")
+        }
+
+        let tag_id = match &self.tag {
+            Some(name) => Some(
+                Tag::get_by_name(conn, name)
+                    .with_context(|| format!("couldn't find a tag named \"{name}\""))?
+                    .id,
+            ),
+            None => None,
+        };
+
+        let items: Vec<Item> = Item::all(conn)
+            .context("couldn't get items from the database")?
+            .filter(|item| tag_id.is_none() || item.tag_id == tag_id)
+            .collect();
+
+        let moved = vacation::reschedule(items, self.from, self.until, Date::today(), self.spread);
+        for item in &moved {
+            item.save(conn)
+                .with_context(|| format!("couldn't save item with ID {}", item.id))?;
+        }
+
+        let vacation = Vacation::record(conn, tag_id, self.from, self.until)?;
+
+        match format {
+            Format::Human => {
+                println!(
+                    "Have a nice vacation! I moved {} items to after you get back:",
+                    moved.len()
+                );
+                for item in &moved {
+                    println!("{}: {} (now due {})", item.id, item.text, item.next);
+                }
+            }
+            Format::Json => println!(
+                "{}",
+                serde_json::to_string(&Output { vacation, moved })
+                    .context("couldn't convert the vacation to JSON")?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn.execute("INSERT INTO tags (name) VALUES ('home')", [])
+            .unwrap();
+        for (tag_id, next) in [
+            (Some(1), Date::ymd(2022, 1, 5)),
+            (None, Date::ymd(2022, 1, 6)),
+            (Some(1), Date::ymd(2022, 2, 1)),
+        ] {
+            conn.execute(
+                "INSERT INTO items (text, cadence, next, tag_id) VALUES ('test', 7, ?, ?)",
+                params![next, tag_id],
+            )
+            .unwrap();
+        }
+
+        conn
+    }
+
+    fn nexts(conn: &Connection) -> Vec<Date> {
+        Item::all(conn).unwrap().map(|item| item.next).collect()
+    }
+
+    #[test]
+    fn moves_items_due_during_vacation() {
+        let conn = conn();
+        let command = Command::try_parse_from([
+            "vacation",
+            "--from",
+            "2022-01-03",
+            "--until",
+            "2022-01-09",
+            "--spread",
+            "1d",
+        ])
+        .unwrap();
+
+        command.run(&conn, Format::Json).unwrap();
+
+        assert_eq!(
+            vec![
+                Date::ymd(2022, 1, 10),
+                Date::ymd(2022, 1, 10),
+                Date::ymd(2022, 2, 1)
+            ],
+            nexts(&conn)
+        );
+        assert_eq!(1, Vacation::all(&conn).unwrap().count());
+    }
+
+    #[test]
+    fn only_moves_tagged_items() {
+        let conn = conn();
+        let command = Command::try_parse_from([
+            "vacation",
+            "--from",
+            "2022-01-03",
+            "--until",
+            "2022-01-09",
+            "--tag",
+            "home",
+        ])
+        .unwrap();
+
+        command.run(&conn, Format::Json).unwrap();
+
+        assert_eq!(
+            vec![
+                Date::ymd(2022, 1, 10),
+                Date::ymd(2022, 1, 6),
+                Date::ymd(2022, 2, 1)
+            ],
+            nexts(&conn)
+        );
+    }
+
+    #[test]
+    fn until_before_from_fails() {
+        let command =
+            Command::try_parse_from(["vacation", "--from", "2022-01-09", "--until", "2022-01-03"])
+                .unwrap();
+
+        assert!(command.run(&conn(), Format::Json).is_err());
+    }
+}
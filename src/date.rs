@@ -1,4 +1,4 @@
-use chrono::{Duration, Local, TimeZone, Utc};
+use chrono::{Duration, Local, NaiveDate, TimeZone, Utc};
 use core::fmt::{self, Display, Formatter};
 use core::ops::{Add, Sub};
 use rusqlite::{
@@ -20,15 +20,23 @@ impl Date {
     pub fn ymd(year: i32, month: u32, day: u32) -> Self {
         Utc.ymd(year, month, day).into()
     }
+
+    /// The day this date falls on in the local timezone. This is what we
+    /// show people, so anything that lays dates out on a calendar should
+    /// use it too.
+    pub fn local(self) -> NaiveDate {
+        self.date.with_timezone(&Local).naive_local()
+    }
+
+    /// Format the (UTC) date with a `strftime`-style format string.
+    pub fn format(self, fmt: &str) -> String {
+        self.date.format(fmt).to_string()
+    }
 }
 
 impl Display for Date {
     fn fmt(&self, out: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            out,
-            "{}",
-            self.date.with_timezone(&Local).format("%A, %B %d, %Y")
-        )
+        write!(out, "{}", self.local().format("%A, %B %d, %Y"))
     }
 }
 
@@ -77,6 +85,62 @@ impl Serialize for Date {
     }
 }
 
+/// (De)serialize a `Date` as a plain `YYYY-MM-DD` string. The regular
+/// `Serialize` impl includes a human-readable date for display, which we
+/// can't read back in, so use this anywhere we need to round-trip dates.
+pub mod ymd {
+    use super::Date;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    static FORMAT: &str = "%Y-%m-%d";
+
+    #[allow(clippy::trivially_copy_pass_by_ref)] // serde requires a reference
+    pub fn serialize<S>(date: &Date, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&date.date.format(FORMAT).to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Date, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+
+        NaiveDate::parse_from_str(&raw, FORMAT)
+            .map(|naive| Utc.from_utc_date(&naive).into())
+            .map_err(de::Error::custom)
+    }
+
+    pub mod option {
+        use super::Date;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        #[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)] // serde requires a reference
+        pub fn serialize<S>(date: &Option<Date>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match date {
+                Some(date) => super::serialize(date, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Date>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(with = "super")] Date);
+
+            Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(date)| date))
+        }
+    }
+}
+
 impl Add<Duration> for Date {
     type Output = Self;
 
@@ -111,6 +175,51 @@ impl From<chrono::Date<Utc>> for Date {
 mod tests {
     use super::*;
 
+    mod ymd {
+        use super::*;
+
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Wrapper {
+            #[serde(with = "crate::date::ymd")]
+            date: Date,
+            #[serde(with = "crate::date::ymd::option")]
+            maybe: Option<Date>,
+        }
+
+        #[test]
+        fn serializes_plain_date() {
+            let wrapper = Wrapper {
+                date: Date::ymd(2022, 1, 2),
+                maybe: None,
+            };
+
+            assert_eq!(
+                r#"{"date":"2022-01-02","maybe":null}"#,
+                serde_json::to_string(&wrapper).unwrap()
+            );
+        }
+
+        #[test]
+        fn roundtrip() {
+            let wrapper = Wrapper {
+                date: Date::ymd(2022, 1, 2),
+                maybe: Some(Date::ymd(2022, 3, 4)),
+            };
+
+            assert_eq!(
+                wrapper,
+                serde_json::from_str(&serde_json::to_string(&wrapper).unwrap()).unwrap()
+            );
+        }
+
+        #[test]
+        fn rejects_other_formats() {
+            assert!(
+                serde_json::from_str::<Wrapper>(r#"{"date":"January 2","maybe":null}"#).is_err()
+            );
+        }
+    }
+
     mod from_sql {
         use super::*;
         use rusqlite::types::{Value, ValueRef};
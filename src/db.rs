@@ -1 +1,267 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, TransactionBehavior};
+use std::path::Path;
+use std::time::Duration;
+
 refinery::embed_migrations!("migrations");
+
+/// How long to wait for another Tempo process (say, a cron job running
+/// `ready` while you're finishing something by hand) to let go of the
+/// database before giving up.
+static BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn open(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path).context("couldn't open the database")?;
+
+    // WAL mode lets readers keep going while a write is in progress, so
+    // concurrent commands only have to wait on each other when both want
+    // to write.
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .context("couldn't switch the database to WAL mode")?;
+
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .context("couldn't set the database's busy timeout")?;
+
+    Ok(conn)
+}
+
+/// The schema version this build of Tempo expects, according to the
+/// migrations embedded in it.
+pub fn latest_version() -> u32 {
+    migrations::runner()
+        .get_migrations()
+        .iter()
+        .map(refinery::Migration::version)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The schema version of the given database, according to refinery's
+/// migration history. Returns `None` if the database has never been
+/// migrated (for example, if it's not a Tempo database at all.)
+pub fn schema_version(conn: &Connection) -> Result<Option<u32>> {
+    let has_history: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'refinery_schema_history')",
+            [],
+            |row| row.get(0),
+        )
+        .context("couldn't look for the migration history table")?;
+
+    if !has_history {
+        return Ok(None);
+    }
+
+    conn.query_row(
+        "SELECT MAX(version) FROM refinery_schema_history",
+        [],
+        |row| row.get(0),
+    )
+    .context("couldn't get the latest migration version")
+}
+
+/// Run `work` in a transaction, committing if it succeeds and rolling back
+/// if it fails. We take the write lock up front (`IMMEDIATE`) so that two
+/// commands can't both read and then deadlock trying to upgrade to a write.
+/// Use this for anything that might write; `read` is for everything else.
+pub fn transaction<T, F>(conn: &mut Connection, work: F) -> Result<T>
+where
+    F: FnOnce(&Connection) -> Result<T>,
+{
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context("couldn't start a transaction")?;
+
+    // if `work` fails, dropping `tx` rolls everything back.
+    let out = work(&tx)?;
+
+    tx.commit().context("couldn't commit the transaction")?;
+
+    Ok(out)
+}
+
+/// Run `work` in a transaction that never takes the write lock, so it sees
+/// one consistent snapshot of the store without waiting on (or holding up)
+/// other commands that are writing. `work` can't write anything.
+pub fn read<T, F>(conn: &mut Connection, work: F) -> Result<T>
+where
+    F: FnOnce(&Connection) -> Result<T>,
+{
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Deferred)
+        .context("couldn't start a transaction")?;
+
+    let out = work(&tx)?;
+
+    // nothing to commit, so this just lets go of the snapshot.
+    tx.rollback().context("couldn't finish the transaction")?;
+
+    Ok(out)
+}
+
+/// Like `transaction`, but always rolls back, even when `work` succeeds.
+/// This is how `--dry-run` shows what a command would do without doing it.
+pub fn dry_run<T, F>(conn: &mut Connection, work: F) -> Result<T>
+where
+    F: FnOnce(&Connection) -> Result<T>,
+{
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context("couldn't start a transaction")?;
+
+    let out = work(&tx)?;
+
+    tx.rollback()
+        .context("couldn't roll back the transaction")?;
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cadence::Cadence;
+    use crate::date::Date;
+    use crate::format::Format;
+    use clap::Parser;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params!["test", Cadence::days(1), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    fn count(conn: &Connection, table: &str) -> u64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn schema_version_of_migrated_database() {
+        assert_eq!(Some(latest_version()), schema_version(&conn()).unwrap());
+    }
+
+    #[test]
+    fn schema_version_of_empty_database() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        assert_eq!(None, schema_version(&conn).unwrap());
+    }
+
+    #[test]
+    fn commits_on_success() {
+        let mut conn = conn();
+
+        transaction(&mut conn, |tx| {
+            tx.execute("DELETE FROM items", [])?;
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(0, count(&conn, "items"));
+    }
+
+    #[test]
+    fn rolls_back_on_failure() {
+        let mut conn = conn();
+
+        let result: Result<()> = transaction(&mut conn, |tx| {
+            tx.execute("DELETE FROM items", [])?;
+            anyhow::bail!("oh no")
+        });
+
+        assert!(result.is_err());
+        assert_eq!(1, count(&conn, "items"));
+    }
+
+    #[test]
+    fn dry_run_rolls_back_on_success() {
+        let mut conn = conn();
+
+        let deleted = dry_run(&mut conn, |tx| Ok(tx.execute("DELETE FROM items", [])?)).unwrap();
+
+        assert_eq!(1, deleted);
+        assert_eq!(1, count(&conn, "items"));
+    }
+
+    #[test]
+    fn reads_do_not_wait_for_writes() {
+        let path = std::env::temp_dir().join(format!(
+            "tempo-test-{}-db-reads.sqlite3",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let mut writer = open(&path).unwrap();
+        migrations::runner().run(&mut writer).unwrap();
+        let mut reader = open(&path).unwrap();
+        reader.busy_timeout(Duration::ZERO).unwrap();
+
+        let count = transaction(&mut writer, |tx| {
+            tx.execute(
+                "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+                params!["test", Cadence::days(1), Date::ymd(2022, 1, 1)],
+            )?;
+
+            // the write lock is held here, but reading still works (and
+            // doesn't see the uncommitted item.)
+            read(&mut reader, |conn| Ok(count(conn, "items")))
+        })
+        .unwrap();
+
+        assert_eq!(0, count);
+
+        drop((writer, reader));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_edit_does_not_leave_orphan_tags() {
+        let mut conn = conn();
+
+        // simulate `Item::save` failing after the new tag has already been
+        // created.
+        conn.execute_batch(
+            "CREATE TRIGGER fail_updates BEFORE UPDATE ON items BEGIN SELECT RAISE(ABORT, 'boom'); END;",
+        )
+        .unwrap();
+
+        let edit =
+            crate::cli::edit::Command::try_parse_from(["edit", "1", "--tag", "new"]).unwrap();
+        let result = transaction(&mut conn, |tx| edit.run(tx, Format::Json));
+
+        assert!(result.is_err());
+        assert_eq!(0, count(&conn, "tags"));
+    }
+
+    #[test]
+    fn failed_delete_keeps_item_out_of_trash() {
+        let mut conn = conn();
+
+        // simulate removing the item failing after it's been copied into
+        // the trash.
+        conn.execute_batch(
+            "CREATE TRIGGER fail_deletes BEFORE DELETE ON items BEGIN SELECT RAISE(ABORT, 'boom'); END;",
+        )
+        .unwrap();
+
+        let delete = crate::cli::delete::Command::try_parse_from(["delete", "1"]).unwrap();
+        let result = transaction(&mut conn, |tx| delete.run(tx, Format::Json));
+
+        assert!(result.is_err());
+        assert_eq!(1, count(&conn, "items"));
+        assert_eq!(0, count(&conn, "trash"));
+    }
+}
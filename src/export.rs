@@ -0,0 +1,584 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::history::Finish;
+use crate::item::{Bump, Item};
+use crate::pid::Pid;
+use crate::tag::Tag;
+use crate::trash::Trashed;
+use crate::vacation::Vacation;
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+/// Bump this whenever the shape of `Document` changes in a way that older
+/// versions of Tempo couldn't read.
+pub static VERSION: u32 = 1;
+
+/// Everything in the store, in a form that can be read back in. Unlike the
+/// regular JSON output, this refers to tags by name (since IDs may differ
+/// between stores) and leaves out derived fields like `human_date`.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Document {
+    pub version: u32,
+    pub tags: Vec<String>,
+    pub items: Vec<ExportedItem>,
+
+    #[serde(default)]
+    pub trash: Vec<ExportedTrash>,
+
+    #[serde(default)]
+    pub history: Vec<ExportedFinish>,
+
+    #[serde(default)]
+    pub vacations: Vec<ExportedVacation>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExportedItem {
+    pub id: u64,
+    pub text: String,
+    pub tag: Option<String>,
+
+    // scheduling
+    pub cadence: Cadence,
+    #[serde(with = "crate::date::ymd")]
+    pub next: Date,
+
+    #[serde(flatten)]
+    pub pid: Pid,
+
+    // retirement
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default, with = "crate::date::ymd::option")]
+    pub paused_until: Option<Date>,
+
+    #[serde(default)]
+    pub learning_steps: u32,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExportedTrash {
+    #[serde(with = "crate::date::ymd")]
+    pub deleted_at: Date,
+
+    #[serde(flatten)]
+    pub item: ExportedItem,
+
+    #[serde(default)]
+    pub history: Vec<ExportedFinish>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExportedFinish {
+    pub item_id: u64,
+    pub bump: Bump,
+    #[serde(with = "crate::date::ymd")]
+    pub due: Date,
+    #[serde(with = "crate::date::ymd")]
+    pub finished_at: Date,
+    pub cadence: Cadence,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExportedVacation {
+    pub tag: Option<String>,
+    #[serde(with = "crate::date::ymd")]
+    pub starts: Date,
+    #[serde(with = "crate::date::ymd")]
+    pub ends: Date,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Added { id: u64, text: String },
+    Updated { id: u64, text: String },
+    Removed { id: u64, text: String },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Add or update items by ID, leaving everything else alone
+    Merge,
+
+    /// Throw away the current store and use the document instead
+    Replace,
+}
+
+impl ExportedItem {
+    fn from_item(item: Item, tag_names: &HashMap<u64, String>) -> ExportedItem {
+        ExportedItem {
+            id: item.id,
+            text: item.text,
+            tag: item.tag_id.and_then(|id| tag_names.get(&id).cloned()),
+            cadence: item.cadence,
+            next: item.next,
+            pid: item.pid,
+            archived: item.archived,
+            paused_until: item.paused_until,
+            learning_steps: item.learning_steps,
+        }
+    }
+
+    fn save(&self, conn: &Connection) -> Result<()> {
+        let tag_id: Option<u64> = match &self.tag {
+            Some(tag_name) => Some(Tag::get_or_create_by_name(conn, tag_name)?.id),
+            None => None,
+        };
+
+        conn.execute(
+            "INSERT INTO items (id, text, tag_id, cadence, next, integral, last_error, archived, paused_until, learning_steps) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10) ON CONFLICT (id) DO UPDATE SET text = ?2, tag_id = ?3, cadence = ?4, next = ?5, integral = ?6, last_error = ?7, archived = ?8, paused_until = ?9, learning_steps = ?10",
+            params![
+                self.id,
+                self.text,
+                tag_id,
+                self.cadence,
+                self.next,
+                self.pid.integral,
+                self.pid.last_error,
+                self.archived,
+                self.paused_until,
+                self.learning_steps,
+            ],
+        )
+        .with_context(|| format!("could not import item with ID {}", self.id))?;
+
+        Ok(())
+    }
+}
+
+impl ExportedTrash {
+    fn from_trashed(trashed: Trashed, conn: &Connection) -> Result<ExportedTrash> {
+        let history = Finish::trashed(conn, trashed.id)?
+            .map(ExportedFinish::from_finish)
+            .collect();
+
+        Ok(ExportedTrash {
+            deleted_at: trashed.deleted_at,
+            item: ExportedItem {
+                id: trashed.item_id,
+                text: trashed.text,
+                tag: trashed.tag,
+                cadence: trashed.cadence,
+                next: trashed.next,
+                pid: trashed.pid,
+                archived: trashed.archived,
+                paused_until: trashed.paused_until,
+                learning_steps: trashed.learning_steps,
+            },
+            history,
+        })
+    }
+
+    fn save(&self, conn: &Connection) -> Result<()> {
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM trash WHERE item_id = ? AND text = ? AND deleted_at = ?)",
+                params![self.item.id, self.item.text, self.deleted_at],
+                |row| row.get(0),
+            )
+            .context("could not check for existing trashed items")?;
+
+        if exists {
+            return Ok(());
+        }
+
+        let trash_id: u64 = conn.query_row(
+            "INSERT INTO trash (item_id, text, tag, cadence, next, integral, last_error, archived, paused_until, deleted_at, learning_steps) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+            params![
+                self.item.id,
+                self.item.text,
+                self.item.tag,
+                self.item.cadence,
+                self.item.next,
+                self.item.pid.integral,
+                self.item.pid.last_error,
+                self.item.archived,
+                self.item.paused_until,
+                self.deleted_at,
+                self.item.learning_steps,
+            ],
+            |row| row.get(0),
+        )
+        .with_context(|| format!("could not import trashed item with ID {}", self.item.id))?;
+
+        for finish in &self.history {
+            finish.save(conn, Some(trash_id))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ExportedFinish {
+    fn from_finish(finish: Finish) -> ExportedFinish {
+        ExportedFinish {
+            item_id: finish.item_id,
+            bump: finish.bump,
+            due: finish.due,
+            finished_at: finish.finished_at,
+            cadence: finish.cadence,
+        }
+    }
+
+    /// Save this finish, as part of the history of something in the trash
+    /// if `trash_id` is given.
+    fn save(&self, conn: &Connection, trash_id: Option<u64>) -> Result<()> {
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM history WHERE item_id = ? AND bump = ? AND due = ? AND finished_at = ? AND trash_id IS ?)",
+                params![self.item_id, self.bump, self.due, self.finished_at, trash_id],
+                |row| row.get(0),
+            )
+            .context("could not check for existing history")?;
+
+        if exists {
+            return Ok(());
+        }
+
+        conn.execute(
+            "INSERT INTO history (item_id, bump, due, finished_at, cadence, trash_id) VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                self.item_id,
+                self.bump,
+                self.due,
+                self.finished_at,
+                self.cadence,
+                trash_id,
+            ],
+        )
+        .with_context(|| format!("could not import history for item with ID {}", self.item_id))?;
+
+        Ok(())
+    }
+}
+
+impl ExportedVacation {
+    fn from_vacation(vacation: &Vacation, tag_names: &HashMap<u64, String>) -> ExportedVacation {
+        ExportedVacation {
+            tag: vacation.tag_id.and_then(|id| tag_names.get(&id).cloned()),
+            starts: vacation.starts,
+            ends: vacation.ends,
+        }
+    }
+
+    fn save(&self, conn: &Connection) -> Result<()> {
+        let tag_id: Option<u64> = match &self.tag {
+            Some(tag_name) => Some(Tag::get_or_create_by_name(conn, tag_name)?.id),
+            None => None,
+        };
+
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM vacations WHERE tag_id IS ? AND starts = ? AND ends = ?)",
+                params![tag_id, self.starts, self.ends],
+                |row| row.get(0),
+            )
+            .context("could not check for existing vacations")?;
+
+        if !exists {
+            Vacation::record(conn, tag_id, self.starts, self.ends)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Document {
+    pub fn from_store(conn: &Connection) -> Result<Document> {
+        let tags: Vec<Tag> = Tag::all(conn).context("could not get tags")?.collect();
+        let tag_names: HashMap<u64, String> =
+            tags.iter().map(|tag| (tag.id, tag.name.clone())).collect();
+
+        Ok(Document {
+            version: VERSION,
+            tags: tags.into_iter().map(|tag| tag.name).collect(),
+            items: Item::all(conn)
+                .context("could not get items")?
+                .map(|item| ExportedItem::from_item(item, &tag_names))
+                .collect(),
+            trash: Trashed::all(conn)
+                .context("could not get trashed items")?
+                .map(|trashed| ExportedTrash::from_trashed(trashed, conn))
+                .collect::<Result<_>>()?,
+            history: Finish::all(conn)
+                .context("could not get history")?
+                .map(ExportedFinish::from_finish)
+                .collect(),
+            vacations: Vacation::all(conn)
+                .context("could not get vacations")?
+                .map(|vacation| ExportedVacation::from_vacation(&vacation, &tag_names))
+                .collect(),
+        })
+    }
+
+    /// Figure out what importing this document would change, without
+    /// changing anything.
+    pub fn plan(&self, mode: Mode, conn: &Connection) -> Result<Vec<Change>> {
+        self.check_version()?;
+
+        let tag_names: HashMap<u64, String> = Tag::all(conn)
+            .context("could not get tags")?
+            .map(|tag| (tag.id, tag.name))
+            .collect();
+
+        let mut existing: HashMap<u64, ExportedItem> = Item::all(conn)
+            .context("could not get items")?
+            .map(|item| (item.id, ExportedItem::from_item(item, &tag_names)))
+            .collect();
+
+        let mut changes = Vec::new();
+
+        for item in &self.items {
+            match existing.remove(&item.id) {
+                None => changes.push(Change::Added {
+                    id: item.id,
+                    text: item.text.clone(),
+                }),
+                Some(current) if current != *item => changes.push(Change::Updated {
+                    id: item.id,
+                    text: item.text.clone(),
+                }),
+                Some(_) => (),
+            }
+        }
+
+        if mode == Mode::Replace {
+            let mut removed: Vec<ExportedItem> = existing.into_values().collect();
+            removed.sort_by_key(|item| item.id);
+
+            changes.extend(removed.into_iter().map(|item| Change::Removed {
+                id: item.id,
+                text: item.text,
+            }));
+        }
+
+        Ok(changes)
+    }
+
+    /// Load this document into the store. Returns the same changes as
+    /// `plan` would have.
+    pub fn import(&self, mode: Mode, conn: &Connection) -> Result<Vec<Change>> {
+        let changes = self.plan(mode, conn)?;
+
+        if mode == Mode::Replace {
+            conn.execute_batch(
+                "DELETE FROM items; DELETE FROM trash; DELETE FROM history; DELETE FROM vacations; DELETE FROM tags;",
+            )
+            .context("could not clear the store")?;
+        }
+
+        for tag in &self.tags {
+            Tag::get_or_create_by_name(conn, tag)?;
+        }
+
+        for item in &self.items {
+            item.save(conn)?;
+        }
+
+        for trashed in &self.trash {
+            trashed.save(conn)?;
+        }
+
+        for finish in &self.history {
+            finish.save(conn, None)?;
+        }
+
+        for vacation in &self.vacations {
+            vacation.save(conn)?;
+        }
+
+        Ok(changes)
+    }
+
+    fn check_version(&self) -> Result<()> {
+        if self.version > VERSION {
+            bail!(
+                "this document was exported by a newer version of Tempo (version {}, but I only know up to {}.) Upgrade Tempo and try again",
+                self.version,
+                VERSION
+            )
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn
+    }
+
+    fn populated() -> Connection {
+        let conn = conn();
+
+        conn.execute("INSERT INTO tags (id, name) VALUES (1, \"tag\")", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO items (text, cadence, next, tag_id, integral, last_error, paused_until) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params!["first", Cadence::weeks(1), Date::ymd(2022, 1, 1), 1, 1.5, -0.5, Date::ymd(2022, 2, 1)],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO items (text, cadence, next, archived) VALUES (?, ?, ?, ?)",
+            params!["second", Cadence::days(3), Date::ymd(2022, 1, 2), true],
+        )
+        .unwrap();
+
+        let third = conn
+            .query_row(
+                "INSERT INTO items (text, next) VALUES (\"third\", ?) RETURNING id",
+                [Date::ymd(2022, 1, 3)],
+                |row| row.get(0),
+            )
+            .unwrap();
+        Finish::record(
+            &conn,
+            &Item::get(third, &conn).unwrap(),
+            &Bump::JustRight,
+            Date::ymd(2021, 12, 24),
+        )
+        .unwrap();
+        Trashed::trash(&Item::get(third, &conn).unwrap(), &conn).unwrap();
+
+        Finish::record(
+            &conn,
+            &Item::get(1, &conn).unwrap(),
+            &Bump::Later,
+            Date::ymd(2021, 12, 25),
+        )
+        .unwrap();
+
+        Vacation::record(
+            &conn,
+            Some(1),
+            Date::ymd(2021, 12, 20),
+            Date::ymd(2021, 12, 31),
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn roundtrips_through_json() {
+        let document = Document::from_store(&populated()).unwrap();
+        let json = serde_json::to_string(&document).unwrap();
+
+        assert_eq!(document, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn uses_tag_names_and_plain_dates() {
+        let document = Document::from_store(&populated()).unwrap();
+        let json = serde_json::to_value(&document).unwrap();
+
+        assert_eq!("tag", json["items"][0]["tag"]);
+        assert_eq!("2022-01-01", json["items"][0]["next"]);
+        assert_eq!(None, json["items"][0].get("tag_id"));
+        assert_eq!("later", json["history"][0]["bump"]);
+        assert_eq!("2021-12-25", json["history"][0]["due"]);
+        assert_eq!("just-right", json["trash"][0]["history"][0]["bump"]);
+        assert_eq!("tag", json["vacations"][0]["tag"]);
+    }
+
+    #[test]
+    fn import_into_empty_store_is_lossless() {
+        let source = populated();
+        let document = Document::from_store(&source).unwrap();
+
+        let target = conn();
+        document.import(Mode::Merge, &target).unwrap();
+
+        assert_eq!(document, Document::from_store(&target).unwrap());
+        assert_eq!(
+            Item::all(&source).unwrap().collect::<Vec<Item>>(),
+            Item::all(&target).unwrap().collect::<Vec<Item>>()
+        );
+    }
+
+    #[test]
+    fn merge_updates_by_id_and_keeps_others() {
+        let conn = populated();
+        let mut document = Document::from_store(&conn).unwrap();
+        document.items.retain(|item| item.id == 1);
+        document.items[0].text = "changed".into();
+
+        let changes = document.import(Mode::Merge, &conn).unwrap();
+
+        assert_eq!(
+            vec![Change::Updated {
+                id: 1,
+                text: "changed".into()
+            }],
+            changes
+        );
+        assert_eq!("changed", Item::get(1, &conn).unwrap().text);
+        assert_eq!("second", Item::get(2, &conn).unwrap().text);
+    }
+
+    #[test]
+    fn merge_does_not_duplicate_trash() {
+        let conn = populated();
+        let document = Document::from_store(&conn).unwrap();
+
+        document.import(Mode::Merge, &conn).unwrap();
+
+        assert_eq!(1, Trashed::all(&conn).unwrap().count());
+        assert_eq!(1, Finish::all(&conn).unwrap().count());
+        assert_eq!(1, Vacation::all(&conn).unwrap().count());
+    }
+
+    #[test]
+    fn replace_removes_missing_items() {
+        let conn = populated();
+        let mut document = Document::from_store(&conn).unwrap();
+        document.items.retain(|item| item.id == 2);
+
+        let changes = document.import(Mode::Replace, &conn).unwrap();
+
+        assert_eq!(
+            vec![Change::Removed {
+                id: 1,
+                text: "first".into()
+            }],
+            changes
+        );
+        assert_eq!(
+            vec![2],
+            Item::all(&conn)
+                .unwrap()
+                .map(|item| item.id)
+                .collect::<Vec<u64>>()
+        );
+    }
+
+    #[test]
+    fn plan_does_not_change_anything() {
+        let source = populated();
+        let document = Document::from_store(&source).unwrap();
+
+        let target = conn();
+        let changes = document.plan(Mode::Merge, &target).unwrap();
+
+        assert_eq!(2, changes.len());
+        assert_eq!(0, Item::all(&target).unwrap().count());
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let mut document = Document::from_store(&conn()).unwrap();
+        document.version = VERSION + 1;
+
+        assert!(document.plan(Mode::Merge, &conn()).is_err());
+    }
+}
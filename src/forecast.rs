@@ -0,0 +1,190 @@
+use crate::date::Date;
+use crate::item::{Bump, Item};
+use crate::tag::Tag;
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use std::collections::HashSet;
+
+/// A day an item is expected to come due.
+#[derive(Debug, PartialEq)]
+pub struct Occurrence {
+    pub date: Date,
+    pub item: Item,
+}
+
+/// Figure out when unarchived items (optionally only those with some tags)
+/// will come due before `until`. Items that are already overdue show up on
+/// their original due date, so callers can decide what to do with them.
+///
+/// With `repeat`, we also project later repetitions by pretending each one
+/// gets finished on the day it's due (or today, if it's overdue) with
+/// `just-right` feedback.
+pub fn occurrences(
+    conn: &Connection,
+    tags: &[String],
+    until: Date,
+    repeat: bool,
+) -> Result<Vec<Occurrence>> {
+    let tag_ids: HashSet<u64> = Tag::all(conn)
+        .context("couldn't get tags")?
+        .filter(|tag| tags.contains(&tag.name))
+        .map(|tag| tag.id)
+        .collect();
+
+    let today = Date::today();
+    let mut out = Vec::new();
+
+    let items = Item::all(conn)
+        .context("couldn't get items from the database")?
+        .filter(|item| !item.archived)
+        .filter(|item| tags.is_empty() || item.tag_id.is_some_and(|id| tag_ids.contains(&id)));
+
+    for mut item in items {
+        // paused items won't show up until the pause is over, whatever
+        // their schedule says.
+        let mut date = match item.paused_until {
+            Some(paused_until) if paused_until > item.next => paused_until,
+            _ => item.next,
+        };
+
+        while date < until {
+            out.push(Occurrence {
+                date,
+                item: item.clone(),
+            });
+
+            if !repeat {
+                break;
+            }
+
+            item.bump_cadence_for_finish(&Bump::JustRight);
+            if item.cadence.days < 1 {
+                // a schedule that never moves forward would repeat forever.
+                break;
+            }
+
+            date = if date > today { date } else { today } + item.cadence;
+        }
+    }
+
+    out.sort_by(|a, b| {
+        a.date
+            .partial_cmp(&b.date)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.item.id.cmp(&b.item.id))
+    });
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cadence::Cadence;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn
+    }
+
+    fn insert(conn: &Connection, text: &str, cadence: Cadence, next: Date) {
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params![text, cadence, next],
+        )
+        .unwrap();
+    }
+
+    fn dates(occurrences: &[Occurrence]) -> Vec<Date> {
+        occurrences
+            .iter()
+            .map(|occurrence| occurrence.date)
+            .collect()
+    }
+
+    #[test]
+    fn only_next_without_repeat() {
+        let conn = conn();
+        let today = Date::today();
+        insert(&conn, "a", Cadence::days(2), today + Cadence::days(1));
+
+        let found = occurrences(&conn, &[], today + Cadence::days(10), false).unwrap();
+
+        assert_eq!(vec![today + Cadence::days(1)], dates(&found));
+    }
+
+    #[test]
+    fn repeats_within_window() {
+        let conn = conn();
+        let today = Date::today();
+        insert(&conn, "a", Cadence::days(3), today + Cadence::days(1));
+
+        let found = occurrences(&conn, &[], today + Cadence::days(10), true).unwrap();
+
+        assert_eq!(
+            vec![
+                today + Cadence::days(1),
+                today + Cadence::days(4),
+                today + Cadence::days(7),
+            ],
+            dates(&found)
+        );
+    }
+
+    #[test]
+    fn overdue_items_repeat_from_today() {
+        let conn = conn();
+        let today = Date::today();
+        insert(&conn, "a", Cadence::days(5), today - Cadence::days(3));
+
+        let found = occurrences(&conn, &[], today + Cadence::days(6), true).unwrap();
+
+        assert_eq!(
+            vec![today - Cadence::days(3), today + Cadence::days(5)],
+            dates(&found)
+        );
+    }
+
+    #[test]
+    fn paused_items_wait_for_the_pause() {
+        let conn = conn();
+        let today = Date::today();
+        insert(&conn, "a", Cadence::days(5), today);
+        conn.execute(
+            "UPDATE items SET paused_until = ?",
+            [today + Cadence::days(2)],
+        )
+        .unwrap();
+
+        let found = occurrences(&conn, &[], today + Cadence::days(3), false).unwrap();
+
+        assert_eq!(vec![today + Cadence::days(2)], dates(&found));
+    }
+
+    #[test]
+    fn skips_archived_and_other_tags() {
+        let conn = conn();
+        let today = Date::today();
+        conn.execute("INSERT INTO tags (name) VALUES ('home')", [])
+            .unwrap();
+        insert(&conn, "untagged", Cadence::days(5), today);
+        insert(&conn, "archived", Cadence::days(5), today);
+        insert(&conn, "tagged", Cadence::days(5), today);
+        conn.execute("UPDATE items SET archived = 1 WHERE id = 2", [])
+            .unwrap();
+        conn.execute("UPDATE items SET tag_id = 1 WHERE id = 3", [])
+            .unwrap();
+
+        let all = occurrences(&conn, &[], today + Cadence::days(1), false).unwrap();
+        assert_eq!(2, all.len());
+
+        let tagged = occurrences(&conn, &["home".into()], today + Cadence::days(1), false).unwrap();
+        assert_eq!(1, tagged.len());
+        assert_eq!("tagged", tagged[0].item.text);
+    }
+}
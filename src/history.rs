@@ -0,0 +1,220 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::item::{Bump, Item};
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, Row};
+
+/// A record of finishing an item, so we can look back at how its schedule
+/// has been going.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Finish {
+    pub id: u64,
+    pub item_id: u64,
+    pub bump: Bump,
+
+    /// When the item was due (its `next` before finishing)
+    pub due: Date,
+    pub finished_at: Date,
+
+    /// The cadence after taking the bump into account
+    pub cadence: Cadence,
+}
+
+impl Finish {
+    fn from_row(row: &'_ Row<'_>) -> rusqlite::Result<Finish> {
+        Ok(Finish {
+            id: row.get(0)?,
+            item_id: row.get(1)?,
+            bump: row.get(2)?,
+            due: row.get(3)?,
+            finished_at: row.get(4)?,
+            cadence: row.get(5)?,
+        })
+    }
+
+    /// Remember that `item` was just finished with `bump`. Call this after
+    /// `Item::finish` with the `next` date from before finishing.
+    pub fn record(conn: &Connection, item: &Item, bump: &Bump, due: Date) -> Result<()> {
+        conn.execute(
+            "INSERT INTO history (item_id, bump, due, finished_at, cadence) VALUES (?, ?, ?, ?, ?)",
+            params![item.id, bump, due, Date::today(), item.cadence],
+        )
+        .with_context(|| format!("could not record finishing item with ID {}", item.id))?;
+
+        Ok(())
+    }
+
+    pub fn all(conn: &Connection) -> Result<impl Iterator<Item = Finish>> {
+        Self::query(
+            conn,
+            "SELECT id, item_id, bump, due, finished_at, cadence FROM history WHERE trash_id IS NULL ORDER BY id ASC",
+            [],
+        )
+        .map(Vec::into_iter)
+    }
+
+    /// Get every finish on or after a date, oldest first.
+    pub fn since(conn: &Connection, date: Date) -> Result<impl Iterator<Item = Finish>> {
+        Self::query(
+            conn,
+            "SELECT id, item_id, bump, due, finished_at, cadence FROM history WHERE trash_id IS NULL AND finished_at >= ? ORDER BY id ASC",
+            [date],
+        )
+        .map(Vec::into_iter)
+    }
+
+    /// Get up to `limit` of an item's most recent finishes, most recent
+    /// first.
+    pub fn recent(
+        conn: &Connection,
+        item_id: u64,
+        limit: usize,
+    ) -> Result<impl Iterator<Item = Finish>> {
+        Self::query(
+            conn,
+            "SELECT id, item_id, bump, due, finished_at, cadence FROM history WHERE trash_id IS NULL AND item_id = ? ORDER BY id DESC LIMIT ?",
+            params![item_id, limit],
+        )
+        .map(Vec::into_iter)
+    }
+
+    /// Get the history of an item that's in the trash, oldest first.
+    pub fn trashed(conn: &Connection, trash_id: u64) -> Result<impl Iterator<Item = Finish>> {
+        Self::query(
+            conn,
+            "SELECT id, item_id, bump, due, finished_at, cadence FROM history WHERE trash_id = ? ORDER BY id ASC",
+            [trash_id],
+        )
+        .map(Vec::into_iter)
+    }
+
+    /// Set an item's history aside along with the item itself when it goes
+    /// in the trash, so a new item that gets the same ID starts fresh.
+    pub fn trash(conn: &Connection, item_id: u64, trash_id: u64) -> Result<()> {
+        conn.execute(
+            "UPDATE history SET trash_id = ? WHERE item_id = ? AND trash_id IS NULL",
+            [trash_id, item_id],
+        )
+        .with_context(|| {
+            format!("could not move history for item with ID {item_id} to the trash")
+        })?;
+
+        Ok(())
+    }
+
+    /// Give a restored item its history back, under whatever ID it was
+    /// restored with.
+    pub fn restore(conn: &Connection, trash_id: u64, item_id: u64) -> Result<()> {
+        conn.execute(
+            "UPDATE history SET item_id = ?, trash_id = NULL WHERE trash_id = ?",
+            [item_id, trash_id],
+        )
+        .with_context(|| format!("could not restore history for item with ID {item_id}"))?;
+
+        Ok(())
+    }
+
+    /// Delete the history of anything that's no longer in the trash, after
+    /// the trash has been emptied.
+    pub fn forget_emptied(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "DELETE FROM history WHERE trash_id IS NOT NULL AND trash_id NOT IN (SELECT id FROM trash)",
+            [],
+        )
+        .context("could not delete history for items removed from the trash")?;
+
+        Ok(())
+    }
+
+    fn query(conn: &Connection, sql: &str, params: impl rusqlite::Params) -> Result<Vec<Finish>> {
+        let mut statement = conn
+            .prepare(sql)
+            .context("could not prepare query to get history")?;
+
+        let finishes = statement
+            .query_map(params, Self::from_row)?
+            .collect::<rusqlite::Result<Vec<Finish>>>()
+            .context("could not pull rows")?;
+
+        Ok(finishes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params!["test", Cadence::days(3), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn records_finishes() {
+        let conn = conn();
+        let item = Item::get(1, &conn).unwrap();
+
+        Finish::record(&conn, &item, &Bump::Later, Date::ymd(2022, 1, 1)).unwrap();
+
+        let finishes: Vec<Finish> = Finish::all(&conn).unwrap().collect();
+        assert_eq!(
+            vec![Finish {
+                id: 1,
+                item_id: 1,
+                bump: Bump::Later,
+                due: Date::ymd(2022, 1, 1),
+                finished_at: Date::today(),
+                cadence: Cadence::days(3),
+            }],
+            finishes
+        );
+    }
+
+    #[test]
+    fn recent_is_most_recent_first() {
+        let conn = conn();
+        let item = Item::get(1, &conn).unwrap();
+
+        Finish::record(&conn, &item, &Bump::Later, Date::ymd(2022, 1, 1)).unwrap();
+        Finish::record(&conn, &item, &Bump::Earlier, Date::ymd(2022, 1, 4)).unwrap();
+        Finish::record(&conn, &item, &Bump::JustRight, Date::ymd(2022, 1, 7)).unwrap();
+
+        let bumps: Vec<Bump> = Finish::recent(&conn, 1, 2)
+            .unwrap()
+            .map(|finish| finish.bump)
+            .collect();
+        assert_eq!(vec![Bump::JustRight, Bump::Earlier], bumps);
+        assert_eq!(0, Finish::recent(&conn, 2, 2).unwrap().count());
+    }
+
+    #[test]
+    fn since_skips_older_finishes() {
+        let conn = conn();
+        conn.execute(
+            "INSERT INTO history (item_id, bump, due, finished_at, cadence) VALUES (1, 'later', ?, ?, 3)",
+            [Date::ymd(2022, 1, 1), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO history (item_id, bump, due, finished_at, cadence) VALUES (1, 'earlier', ?, ?, 3)",
+            [Date::ymd(2022, 2, 1), Date::ymd(2022, 2, 1)],
+        )
+        .unwrap();
+
+        let bumps: Vec<Bump> = Finish::since(&conn, Date::ymd(2022, 1, 15))
+            .unwrap()
+            .map(|finish| finish.bump)
+            .collect();
+        assert_eq!(vec![Bump::Earlier], bumps);
+    }
+}
@@ -0,0 +1,340 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::item::Item;
+use crate::tag::Tag;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// The longest a line in an iCalendar file can be, in bytes, before it has
+/// to be folded onto the next line (RFC 5545, section 3.1.)
+static MAX_LINE_LENGTH: usize = 75;
+
+#[derive(Debug, PartialEq)]
+pub struct Event {
+    pub uid: String,
+    pub date: Date,
+    pub summary: String,
+    pub description: String,
+    pub category: Option<String>,
+}
+
+impl Event {
+    /// Get the events for an item: one on its next date, plus (if `until`
+    /// is present) one for every time it would come up again before then,
+    /// assuming its cadence doesn't change. Like `tempo calendar --repeat`,
+    /// an overdue item's repeats count from `today`, since that's the
+    /// earliest it could be finished.
+    pub fn for_item(
+        item: &Item,
+        tag: Option<&str>,
+        today: Date,
+        until: Option<Date>,
+    ) -> Vec<Event> {
+        let mut events = vec![Self::occurrence(item, tag, item.next, 0)];
+
+        if let Some(until) = until {
+            // a cadence of zero or less would never move forward.
+            if item.cadence > Cadence::days(0) {
+                let mut date = if item.next > today { item.next } else { today } + item.cadence;
+
+                while date <= until {
+                    events.push(Self::occurrence(item, tag, date, events.len()));
+                    date = date + item.cadence;
+                }
+            }
+        }
+
+        events
+    }
+
+    fn occurrence(item: &Item, tag: Option<&str>, date: Date, index: usize) -> Event {
+        // UIDs have to be stable across exports so calendar apps update
+        // events instead of duplicating them.
+        let uid = if index == 0 {
+            format!("tempo-item-{}@tempo", item.id)
+        } else {
+            format!("tempo-item-{}-{}@tempo", item.id, index)
+        };
+
+        Event {
+            uid,
+            date,
+            summary: item.text.clone(),
+            description: format!("Tempo item {}, every {}", item.id, item.cadence),
+            category: tag.map(str::to_string),
+        }
+    }
+}
+
+/// Render the events as an iCalendar document.
+pub fn render(events: &[Event], stamp: DateTime<Utc>) -> String {
+    let mut out = String::new();
+
+    line(&mut out, "BEGIN:VCALENDAR");
+    line(&mut out, "VERSION:2.0");
+    line(&mut out, "PRODID:-//tempo//tempo//EN");
+    line(&mut out, "CALSCALE:GREGORIAN");
+
+    for event in events {
+        let end = event.date + Cadence::days(1);
+
+        line(&mut out, "BEGIN:VEVENT");
+        line(&mut out, &format!("UID:{}", event.uid));
+        line(
+            &mut out,
+            &format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+        );
+        line(
+            &mut out,
+            &format!("DTSTART;VALUE=DATE:{}", event.date.format("%Y%m%d")),
+        );
+        line(
+            &mut out,
+            &format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")),
+        );
+        line(&mut out, &format!("SUMMARY:{}", escape(&event.summary)));
+        line(
+            &mut out,
+            &format!("DESCRIPTION:{}", escape(&event.description)),
+        );
+        if let Some(category) = &event.category {
+            line(&mut out, &format!("CATEGORIES:{}", escape(category)));
+        }
+        line(&mut out, "TRANSP:TRANSPARENT");
+        line(&mut out, "END:VEVENT");
+    }
+
+    line(&mut out, "END:VCALENDAR");
+
+    out
+}
+
+/// Get a calendar of every active item, optionally limited to some tags and
+/// projected forward by `horizon`.
+pub fn calendar(conn: &Connection, tags: &[String], horizon: Option<Cadence>) -> Result<String> {
+    let tag_names: HashMap<u64, String> = Tag::all(conn)
+        .context("couldn't get tags")?
+        .map(|tag| (tag.id, tag.name))
+        .collect();
+
+    let today = Date::today();
+    let until = horizon.map(|horizon| today + horizon);
+
+    let events: Vec<Event> = Item::all(conn)
+        .context("couldn't get items from the database")?
+        .filter(|item| !item.archived)
+        .filter_map(|item| {
+            let tag = item.tag_id.and_then(|id| tag_names.get(&id));
+
+            if tags.is_empty() || tag.is_some_and(|name| tags.contains(name)) {
+                Some(Event::for_item(
+                    &item,
+                    tag.map(String::as_str),
+                    today,
+                    until,
+                ))
+            } else {
+                None
+            }
+        })
+        .flatten()
+        .collect();
+
+    Ok(render(&events, Utc::now()))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Write a content line, folding it if it's too long. Folds can't split a
+/// UTF-8 character, so we look for the closest character boundary.
+fn line(out: &mut String, content: &str) {
+    let mut rest = content;
+    let mut limit = MAX_LINE_LENGTH;
+
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+
+        let _ = write!(out, "{}\r\n ", &rest[..split]);
+        rest = &rest[split..];
+
+        // continuation lines start with a space, which counts against the
+        // limit.
+        limit = MAX_LINE_LENGTH - 1;
+    }
+
+    let _ = write!(out, "{rest}\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pid::Pid;
+    use chrono::TimeZone;
+
+    fn item() -> Item {
+        Item {
+            id: 1,
+            text: "Call Julie, maybe; or text".into(),
+            tag_id: None,
+            cadence: Cadence::weeks(1),
+            next: Date::ymd(2022, 1, 1),
+            pid: Pid::default(),
+            archived: false,
+            paused_until: None,
+            learning_steps: 0,
+        }
+    }
+
+    #[test]
+    fn single_event_without_horizon() {
+        let events = Event::for_item(&item(), None, Date::ymd(2022, 1, 1), None);
+
+        assert_eq!(1, events.len());
+        assert_eq!("tempo-item-1@tempo", events[0].uid);
+        assert_eq!(Date::ymd(2022, 1, 1), events[0].date);
+    }
+
+    #[test]
+    fn projects_occurrences_up_to_horizon() {
+        let events = Event::for_item(
+            &item(),
+            None,
+            Date::ymd(2022, 1, 1),
+            Some(Date::ymd(2022, 1, 15)),
+        );
+
+        assert_eq!(
+            vec![
+                Date::ymd(2022, 1, 1),
+                Date::ymd(2022, 1, 8),
+                Date::ymd(2022, 1, 15)
+            ],
+            events.iter().map(|event| event.date).collect::<Vec<Date>>()
+        );
+        assert_eq!("tempo-item-1-2@tempo", events[2].uid);
+    }
+
+    #[test]
+    fn non_positive_cadence_does_not_loop() {
+        let mut item = item();
+        item.cadence = Cadence::days(0);
+
+        assert_eq!(
+            1,
+            Event::for_item(
+                &item,
+                None,
+                Date::ymd(2022, 1, 1),
+                Some(Date::ymd(2022, 2, 1))
+            )
+            .len()
+        );
+    }
+
+    #[test]
+    fn overdue_items_repeat_from_today() {
+        let events = Event::for_item(
+            &item(),
+            None,
+            Date::ymd(2022, 1, 10),
+            Some(Date::ymd(2022, 1, 24)),
+        );
+
+        assert_eq!(
+            vec![
+                Date::ymd(2022, 1, 1),
+                Date::ymd(2022, 1, 17),
+                Date::ymd(2022, 1, 24)
+            ],
+            events.iter().map(|event| event.date).collect::<Vec<Date>>()
+        );
+    }
+
+    #[test]
+    fn renders_all_day_events() {
+        let rendered = render(
+            &Event::for_item(&item(), Some("people"), Date::ymd(2022, 1, 1), None),
+            Utc.ymd(2022, 1, 1).and_hms(12, 0, 0),
+        );
+
+        assert!(rendered.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(rendered.ends_with("END:VCALENDAR\r\n"));
+        assert!(rendered.contains("DTSTAMP:20220101T120000Z\r\n"));
+        assert!(rendered.contains("DTSTART;VALUE=DATE:20220101\r\n"));
+        assert!(rendered.contains("DTEND;VALUE=DATE:20220102\r\n"));
+        assert!(rendered.contains("SUMMARY:Call Julie\\, maybe\\; or text\r\n"));
+        assert!(rendered.contains("CATEGORIES:people\r\n"));
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let mut out = String::new();
+        line(&mut out, &"é".repeat(100));
+
+        for folded in out.split("\r\n") {
+            assert!(folded.len() <= MAX_LINE_LENGTH);
+        }
+        assert_eq!("é".repeat(100), out.replace("\r\n ", "").trim_end());
+    }
+
+    mod calendar {
+        use super::*;
+        use rusqlite::params;
+
+        fn conn() -> Connection {
+            let mut conn =
+                Connection::open_in_memory().expect("couldn't open an in-memory database");
+            crate::db::migrations::runner()
+                .run(&mut conn)
+                .expect("couldn't migrate database");
+
+            conn.execute("INSERT INTO tags (id, name) VALUES (1, \"people\")", [])
+                .unwrap();
+            conn.execute(
+                "INSERT INTO items (text, cadence, next, tag_id) VALUES (?, ?, ?, ?)",
+                params!["tagged", Cadence::weeks(1), Date::today(), 1],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+                params!["untagged", Cadence::weeks(1), Date::today()],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO items (text, cadence, next, archived) VALUES (?, ?, ?, ?)",
+                params!["archived", Cadence::weeks(1), Date::today(), true],
+            )
+            .unwrap();
+
+            conn
+        }
+
+        #[test]
+        fn skips_archived_items() {
+            let rendered = calendar(&conn(), &[], None).unwrap();
+
+            assert!(rendered.contains("SUMMARY:tagged"));
+            assert!(rendered.contains("SUMMARY:untagged"));
+            assert!(!rendered.contains("SUMMARY:archived"));
+        }
+
+        #[test]
+        fn filters_by_tag() {
+            let rendered = calendar(&conn(), &["people".into()], None).unwrap();
+
+            assert!(rendered.contains("SUMMARY:tagged"));
+            assert!(!rendered.contains("SUMMARY:untagged"));
+        }
+    }
+}
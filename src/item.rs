@@ -1,11 +1,19 @@
 use crate::cadence::Cadence;
 use crate::date::Date;
 use crate::pid::Pid;
-use anyhow::{bail, Context, Result};
+use crate::status::Suggestion;
+use crate::tag::Tag;
+use anyhow::{Context, Result};
 use chrono::Utc;
-use rusqlite::{params, Connection, Row};
+use clap::ArgEnum;
+use rusqlite::types::{FromSql, FromSqlError, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, Row, ToSql};
+use thiserror::Error;
 
-#[derive(Debug, serde::Serialize, PartialEq)]
+/// How many learning steps new items get, unless they say otherwise.
+pub static DEFAULT_LEARNING_STEPS: u32 = 3;
+
+#[derive(Clone, Debug, serde::Serialize, PartialEq)]
 pub struct Item {
     pub id: u64,
     pub text: String,
@@ -17,9 +25,19 @@ pub struct Item {
 
     #[serde(flatten)]
     pub pid: Pid,
+
+    // retirement
+    pub archived: bool,
+    pub paused_until: Option<Date>,
+
+    /// How many more finishes should make big jumps in the cadence (see
+    /// `Item::bump_cadence_for_finish`) before the PID controller takes
+    /// over.
+    pub learning_steps: u32,
 }
 
-#[derive(clap::ArgEnum, Clone, Debug)]
+#[derive(clap::ArgEnum, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Bump {
     MuchEarlier,
     Earlier,
@@ -28,6 +46,41 @@ pub enum Bump {
     MuchLater,
 }
 
+impl Bump {
+    /// The name we use for this bump on the command line (and everywhere
+    /// else, like the history table.)
+    pub fn name(&self) -> &'static str {
+        self.to_possible_value()
+            .map_or("", |value| value.get_name())
+    }
+}
+
+impl ToSql for Bump {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Borrowed(ValueRef::Text(
+            self.name().as_bytes(),
+        )))
+    }
+}
+
+impl FromSql for Bump {
+    fn column_result(value: ValueRef<'_>) -> Result<Self, FromSqlError> {
+        Bump::from_str(value.as_str()?, false).map_err(|err| FromSqlError::Other(err.into()))
+    }
+}
+
+/// Reasons an item can't be finished right now. These are separate from
+/// other errors so that callers (like `tempo serve`) can tell them apart.
+#[derive(Debug, Error, PartialEq)]
+pub enum FinishError {
+    #[error("can't finish an archived item (unarchive it first)")]
+    Archived,
+    #[error("can't finish an item while it's paused ({0})")]
+    Paused(Date),
+    #[error("can't finish an item before it's due ({0})")]
+    NotDue(Date),
+}
+
 impl Item {
     fn from_row(row: &'_ Row<'_>) -> rusqlite::Result<Item> {
         Ok(Item {
@@ -40,12 +93,15 @@ impl Item {
                 integral: row.get(5)?,
                 last_error: row.get(6)?,
             },
+            archived: row.get(7)?,
+            paused_until: row.get(8)?,
+            learning_steps: row.get(9)?,
         })
     }
 
     pub fn get(id: u64, conn: &Connection) -> Result<Item> {
         conn.query_row(
-            "SELECT id, text, tag_id, cadence, next, integral, last_error FROM items WHERE id = ?",
+            "SELECT id, text, tag_id, cadence, next, integral, last_error, archived, paused_until, learning_steps FROM items WHERE id = ?",
             [id],
             Self::from_row,
         )
@@ -54,7 +110,7 @@ impl Item {
 
     pub fn all(conn: &Connection) -> Result<impl Iterator<Item = Item>> {
         let mut statement = conn
-            .prepare("SELECT id, text, tag_id, cadence, next, integral, last_error FROM items ORDER BY id ASC")
+            .prepare("SELECT id, text, tag_id, cadence, next, integral, last_error, archived, paused_until, learning_steps FROM items ORDER BY id ASC")
             .context("could not prepare query to get all items")?;
 
         let items = statement
@@ -65,11 +121,74 @@ impl Item {
         Ok(items.into_iter())
     }
 
-    pub fn due(conn: &Connection) -> Result<impl Iterator<Item = Item>> {
-        let mut statement = conn.prepare("SELECT id, text, tag_id, cadence, next, integral, last_error FROM items WHERE next <= ? ORDER BY next ASC").context("could not prepare query to get items")?;
+    /// Add a new item. If only one of `cadence` and `next` is given, we
+    /// figure out the other from it, and if neither is, we start from the
+    /// settled items in the same tag (or daily, if there aren't any.) Along
+    /// with the item, this returns the suggestion its cadence came from, if
+    /// it came from one.
+    pub fn create(
+        text: &str,
+        tag: Option<&str>,
+        cadence: Option<Cadence>,
+        next: Option<Date>,
+        learning_steps: u32,
+        conn: &Connection,
+    ) -> Result<(Item, Option<Suggestion>)> {
+        let tag_id: Option<u64> = match tag {
+            Some(tag_name) => Some(Tag::get_or_create_by_name(conn, tag_name)?.id),
+            None => None,
+        };
+
+        let suggestion = match (tag_id, cadence, next) {
+            (Some(tag_id), None, None) => Suggestion::for_tag(tag_id, conn)?,
+            _ => None,
+        };
+        let fallback = suggestion
+            .as_ref()
+            .map_or_else(Cadence::default, |suggestion| suggestion.cadence);
+
+        let today = Date::today();
+        let (cadence, next) = match (cadence, next) {
+            (Some(cadence), Some(next)) => (cadence, next),
+            (Some(cadence), None) => (cadence, today + cadence),
+            // a `next` of today or earlier doesn't say anything useful about
+            // the cadence, so start from the shortest one we allow
+            (None, Some(next)) => (Cadence::days((next - today).num_days().max(1)), next),
+            (None, None) => (fallback, today + fallback),
+        };
+
+        let id: u64 = conn
+            .query_row(
+                "INSERT INTO items (text, cadence, next, tag_id, learning_steps) VALUES (?, ?, ?, ?, ?) RETURNING id",
+                params![text, cadence, next, tag_id, learning_steps],
+                |row| row.get(0),
+            )
+            .context("could not insert the new row into the database")?;
+
+        Ok((Item::get(id, conn)?, suggestion))
+    }
+
+    /// Find an item whose text matches the given text, ignoring differences
+    /// in case and whitespace. Archived items count too, since adding a
+    /// second copy of one of those is probably a mistake as well.
+    pub fn find_duplicate(text: &str, conn: &Connection) -> Result<Option<Item>> {
+        let normalized = normalize(text);
+
+        Ok(Self::all(conn)
+            .context("could not get items to check for duplicates")?
+            .find(|item| normalize(&item.text) == normalized))
+    }
+
+    /// Items that are due (and not paused), leaving out archived items
+    /// unless `include_archived` is set.
+    pub fn due(conn: &Connection, include_archived: bool) -> Result<impl Iterator<Item = Item>> {
+        let mut statement = conn.prepare("SELECT id, text, tag_id, cadence, next, integral, last_error, archived, paused_until, learning_steps FROM items WHERE (? OR archived = 0) AND next <= ? AND (paused_until IS NULL OR paused_until <= ?) ORDER BY next ASC").context("could not prepare query to get items")?;
 
         let items = statement
-            .query_map([Utc::now()], Self::from_row)?
+            .query_map(
+                params![include_archived, Utc::now(), Date::today()],
+                Self::from_row,
+            )?
             .collect::<rusqlite::Result<Vec<Item>>>()
             .context("could not pull rows")?;
 
@@ -78,7 +197,7 @@ impl Item {
 
     pub fn save(&self, conn: &Connection) -> Result<()> {
         conn.execute(
-            "UPDATE items SET text = ?, cadence = ?, next = ?, tag_id = ?, integral = ?, last_error = ? WHERE id = ?",
+            "UPDATE items SET text = ?, cadence = ?, next = ?, tag_id = ?, integral = ?, last_error = ?, archived = ?, paused_until = ?, learning_steps = ? WHERE id = ?",
             params![
                 self.text,
                 self.cadence,
@@ -86,6 +205,9 @@ impl Item {
                 self.tag_id,
                 self.pid.integral,
                 self.pid.last_error,
+                self.archived,
+                self.paused_until,
+                self.learning_steps,
                 self.id,
             ]
         ).with_context(|| format!("could not item with ID {}", self.id))?;
@@ -111,15 +233,64 @@ impl Item {
         adjustment
     }
 
-    pub fn finish(&mut self, bump: &Bump) -> Result<Cadence> {
+    /// Change the cadence the way finishing with this bump would: a learning
+    /// step if there are any left, and the PID controller otherwise. Only
+    /// finishes (and projections of them) count as learning steps; bumping
+    /// in `tempo edit` always goes through `bump_cadence`.
+    pub fn bump_cadence_for_finish(&mut self, bump: &Bump) -> Cadence {
+        if self.learning_steps > 0 {
+            self.learning_step(bump)
+        } else {
+            self.bump_cadence(bump)
+        }
+    }
+
+    /// New items start with a guess at the cadence, which could be way off.
+    /// The PID controller only moves a few days at a time, so for the first
+    /// few finishes we multiply or divide the cadence instead, like Anki's
+    /// learning steps. This leaves the PID state alone so that it starts
+    /// fresh once the item graduates.
+    fn learning_step(&mut self, bump: &Bump) -> Cadence {
+        let days = self.cadence.days;
+        let stepped = match bump {
+            Bump::MuchEarlier => days / 4,
+            Bump::Earlier => days / 2,
+            Bump::JustRight => days,
+            Bump::Later => days * 2,
+            Bump::MuchLater => days * 4,
+        }
+        .max(1);
+
+        self.learning_steps -= 1;
+
+        let adjustment = Cadence::days(stepped - days);
+        log::debug!(
+            "learning step: adjusting cadence by {:?} ({} steps left)",
+            adjustment,
+            self.learning_steps
+        );
+        self.cadence += adjustment;
+
+        adjustment
+    }
+
+    pub fn finish(&mut self, bump: &Bump) -> Result<Cadence, FinishError> {
         let today = Date::today();
 
         log::debug!("next: {}, now: {}", self.next, today);
+        if self.archived {
+            return Err(FinishError::Archived);
+        }
+
+        if let Some(paused_until) = self.paused_until.filter(|until| *until > today) {
+            return Err(FinishError::Paused(paused_until));
+        }
+
         if self.next > today {
-            bail!("can't finish an item before it's due ({})", self.next)
+            return Err(FinishError::NotDue(self.next));
         }
 
-        let adjustment = self.bump_cadence(bump);
+        let adjustment = self.bump_cadence_for_finish(bump);
 
         self.next = today + self.cadence;
 
@@ -127,6 +298,13 @@ impl Item {
     }
 }
 
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,6 +318,60 @@ mod tests {
             cadence: Cadence::days(1),
             next: Utc.ymd(2022, 1, 1).into(),
             pid: Pid::default(),
+            archived: false,
+            paused_until: None,
+            learning_steps: 0,
+        }
+    }
+
+    mod find_duplicate {
+        use super::*;
+
+        fn conn() -> Connection {
+            let mut conn =
+                Connection::open_in_memory().expect("couldn't open an in-memory database");
+            crate::db::migrations::runner()
+                .run(&mut conn)
+                .expect("couldn't migrate database");
+
+            conn.execute(
+                "INSERT INTO items (text, next) VALUES (?, ?)",
+                params!["Call  Julie", Date::today()],
+            )
+            .unwrap();
+
+            conn
+        }
+
+        #[test]
+        fn finds_identical_text() {
+            let conn = conn();
+
+            assert_eq!(
+                Some(1),
+                Item::find_duplicate("Call  Julie", &conn)
+                    .unwrap()
+                    .map(|item| item.id)
+            );
+        }
+
+        #[test]
+        fn ignores_case_and_whitespace() {
+            let conn = conn();
+
+            assert_eq!(
+                Some(1),
+                Item::find_duplicate(" call julie\n", &conn)
+                    .unwrap()
+                    .map(|item| item.id)
+            );
+        }
+
+        #[test]
+        fn different_text() {
+            let conn = conn();
+
+            assert_eq!(None, Item::find_duplicate("Call Marcus", &conn).unwrap());
         }
     }
 
@@ -199,6 +431,62 @@ mod tests {
 
             assert!(large.cadence > small.cadence);
         }
+
+        #[test]
+        fn learning_steps_multiply() {
+            let mut item = default();
+            item.cadence = Cadence::days(8);
+            item.learning_steps = 3;
+
+            item.bump_cadence_for_finish(&Bump::Later);
+            assert_eq!(Cadence::days(16), item.cadence);
+
+            item.bump_cadence_for_finish(&Bump::MuchEarlier);
+            assert_eq!(Cadence::days(4), item.cadence);
+
+            item.bump_cadence_for_finish(&Bump::JustRight);
+            assert_eq!(Cadence::days(4), item.cadence);
+
+            assert_eq!(0, item.learning_steps);
+            assert_eq!(Pid::default(), item.pid);
+        }
+
+        #[test]
+        fn learning_steps_stop_at_a_day() {
+            let mut item = default();
+            item.cadence = Cadence::days(2);
+            item.learning_steps = 1;
+
+            item.bump_cadence_for_finish(&Bump::MuchEarlier);
+
+            assert_eq!(Cadence::days(1), item.cadence);
+        }
+
+        #[test]
+        fn pid_takes_over_after_learning() {
+            let mut item = default();
+            item.cadence = Cadence::days(8);
+            item.learning_steps = 1;
+
+            item.bump_cadence_for_finish(&Bump::Later);
+            item.bump_cadence_for_finish(&Bump::Later);
+
+            assert!(item.cadence > Cadence::days(16));
+            assert!(item.cadence < Cadence::days(32));
+        }
+
+        #[test]
+        fn plain_bumps_skip_learning_steps() {
+            let mut item = default();
+            item.cadence = Cadence::days(8);
+            item.learning_steps = 3;
+
+            item.bump_cadence(&Bump::Later);
+
+            assert!(item.cadence > Cadence::days(8));
+            assert!(item.cadence < Cadence::days(16));
+            assert_eq!(3, item.learning_steps);
+        }
     }
 
     mod finish {
@@ -215,6 +503,33 @@ mod tests {
             );
         }
 
+        #[test]
+        fn disallows_archived_items() {
+            let mut item = default();
+            item.next = Date::today() - item.cadence;
+            item.archived = true;
+
+            assert_eq!(
+                "can't finish an archived item (unarchive it first)",
+                item.finish(&Bump::JustRight).unwrap_err().to_string()
+            );
+        }
+
+        #[test]
+        fn disallows_paused_items() {
+            let mut item = default();
+            item.next = Date::today() - item.cadence;
+            item.paused_until = Some(Date::today() + Cadence::weeks(1));
+
+            assert_eq!(
+                format!(
+                    "can't finish an item while it's paused ({})",
+                    Date::today() + Cadence::weeks(1)
+                ),
+                item.finish(&Bump::JustRight).unwrap_err().to_string()
+            );
+        }
+
         #[test]
         fn moves_into_the_future() {
             let mut item = default();
@@ -226,4 +541,65 @@ mod tests {
             assert!(old_next < item.next);
         }
     }
+
+    mod create {
+        use super::*;
+
+        fn conn() -> Connection {
+            let mut conn =
+                Connection::open_in_memory().expect("couldn't open an in-memory database");
+            crate::db::migrations::runner()
+                .run(&mut conn)
+                .expect("couldn't migrate database");
+
+            conn
+        }
+
+        fn create(cadence: Option<Cadence>, next: Option<Date>) -> Item {
+            Item::create("Text", None, cadence, next, DEFAULT_LEARNING_STEPS, &conn())
+                .unwrap()
+                .0
+        }
+
+        #[test]
+        fn next_is_used() {
+            let next = Date::today() + Cadence::weeks(1);
+
+            assert_eq!(next, create(None, Some(next)).next);
+        }
+
+        #[test]
+        fn next_is_calculated_based_on_cadence() {
+            let cadence = Cadence::days(3);
+
+            assert_eq!(Date::today() + cadence, create(Some(cadence), None).next);
+        }
+
+        #[test]
+        fn cadence_is_used() {
+            let cadence = Cadence::weeks(1);
+
+            assert_eq!(cadence, create(Some(cadence), None).cadence);
+        }
+
+        #[test]
+        fn cadence_is_calculated_based_on_next() {
+            let next = Date::today() + Cadence::weeks(1);
+
+            assert_eq!(Cadence::weeks(1), create(None, Some(next)).cadence);
+        }
+
+        #[test]
+        fn cadence_is_at_least_a_day_if_next_is_not_in_the_future() {
+            assert_eq!(Cadence::days(1), create(None, Some(Date::today())).cadence);
+
+            let next = Date::today() - Cadence::weeks(1);
+            assert_eq!(Cadence::days(1), create(None, Some(next)).cadence);
+        }
+
+        #[test]
+        fn cadence_is_one_day_if_neither_is_present() {
+            assert_eq!(Cadence::days(1), create(None, None).cadence);
+        }
+    }
 }
@@ -1,14 +1,29 @@
 #![deny(clippy::all)]
 #![warn(clippy::pedantic)]
 
+mod api;
+mod bulk;
 mod cadence;
 mod cli;
 mod date;
 mod db;
+mod export;
+mod forecast;
 mod format;
+mod history;
+mod ics;
 mod item;
 mod pid;
+mod reschedule;
+mod retire;
+mod review;
+mod rpc;
+mod server;
+mod sort;
+mod status;
 mod tag;
+mod trash;
+mod vacation;
 
 use crate::format::Format;
 use anyhow::{Context, Result};
@@ -34,6 +49,12 @@ struct Opts {
     /// Support`, etc.)
     #[clap(long, short, env = "TEMPO_DB_PATH", global = true)]
     db_path: Option<PathBuf>,
+
+    /// Run the command as usual, but throw away any changes it makes
+    /// instead of saving them. Useful for trying out scripts against your
+    /// real database.
+    #[clap(long, global = true)]
+    dry_run: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -50,31 +71,198 @@ enum Command {
     #[clap(alias = "pull")]
     Ready(cli::ready::Command),
 
+    /// Count how many items will come due each day for a while
+    Forecast(cli::forecast::Command),
+
+    /// Show a month at a glance, with how many items are due each day
+    Calendar(cli::calendar::Command),
+
+    /// Summarize how the schedules are doing: cadences per tag, what's
+    /// overdue, what's still settling in, and recent feedback
+    Stats(cli::stats::Command),
+
+    /// Go through the items that are ready one at a time, finishing,
+    /// snoozing, or editing each with a single key
+    Review(cli::review::Command),
+
+    /// Show everything about an item, including its scheduling state
+    Show(cli::show::Command),
+
     /// Edit an existing item
     Edit(cli::edit::Command),
 
     /// Finish a due item
     Finish(cli::finish::Command),
 
-    /// Delete an item
+    /// Move the next dates of many items at once, without changing their
+    /// cadences
+    Reschedule(cli::reschedule::Command),
+
+    /// Delete an item (it'll go to the trash, so you can get it back later)
     #[clap(alias = "drop")]
     Delete(cli::delete::Command),
+
+    /// Archive an item, keeping its schedule around but hiding it from
+    /// "ready" and "all" until it's unarchived
+    Archive(cli::archive::Command),
+
+    /// Bring back an archived or paused item
+    Unarchive(cli::unarchive::Command),
+
+    /// List items that keep getting pushed away, and archive them if you
+    /// don't want to see them anymore
+    Retire(cli::retire::Command),
+
+    /// Hide an item from "ready" until a given date
+    Pause(cli::pause::Command),
+
+    /// Move everything that would come due while you're away to just after
+    /// you get back
+    Vacation(cli::vacation::Command),
+
+    /// Look at, restore, or empty deleted items
+    Trash(cli::trash::Command),
+
+    /// Write a consistent snapshot of the database somewhere else
+    Backup(cli::backup::Command),
+
+    /// Replace the database with a backup
+    Restore(cli::restore::Command),
+
+    /// Dump everything in the store as JSON that can be read back in with
+    /// "import" (for example, to move to another machine), or as an
+    /// iCalendar file of upcoming due dates
+    Export(cli::export::Command),
+
+    /// Load items from a file made by "export", or add a bunch of items at
+    /// once from CSV or Markdown
+    Import(cli::import::Command),
+
+    /// Serve read-only JSON and iCalendar feeds over HTTP, for calendar apps
+    /// and dashboards that would rather subscribe to a URL
+    Serve(cli::serve::Command),
+
+    /// Speak JSON-RPC 2.0 over stdin and stdout (one message per line) for
+    /// editor plugins and other tools that want to keep Tempo running.
+    /// Output is always JSON, regardless of --format.
+    Rpc(cli::rpc::Command),
 }
 
 impl Opts {
     fn try_main(&self) -> Result<()> {
         let mut conn = self.get_store()?;
+
+        if self.dry_run {
+            self.check_dry_run(&conn)?;
+        }
+
         db::migrations::runner()
             .run(&mut conn)
             .context("couldn't migrate the database's data!")?;
 
+        let format = self.format;
         match &self.command {
-            Command::Add(add) => add.run(&conn, self.format),
-            Command::All(all) => all.run(&conn, self.format),
-            Command::Ready(ready) => ready.run(&conn, self.format),
-            Command::Edit(edit) => edit.run(&conn, self.format),
-            Command::Finish(finish) => finish.run(&conn, self.format),
-            Command::Delete(delete) => delete.run(&conn, self.format),
+            // SQLite's backup API can't copy into or out of a connection
+            // that's in the middle of a transaction, so these two work on
+            // the connection directly.
+            Command::Backup(backup) => backup.run(&conn, &self.get_db_path()?, format),
+            Command::Restore(restore) => restore.run(&mut conn, format),
+
+            // these run until they're stopped, so they start a new
+            // transaction for each request instead of holding one open.
+            Command::Serve(serve) => serve.run(&mut conn, format),
+            Command::Rpc(rpc) => rpc.run(&mut conn),
+
+            // reviewing can take a while, so save each change as it's made
+            // instead of holding a transaction open the whole time.
+            Command::Review(review) => review.run(&mut conn, format),
+
+            // commands that only look at the store don't need the write
+            // lock, so they never have to wait for (or hold up) a write.
+            Command::All(all) => db::read(&mut conn, |conn| all.run(conn, format)),
+            Command::Ready(ready) => db::read(&mut conn, |conn| ready.run(conn, format)),
+            Command::Forecast(forecast) => db::read(&mut conn, |conn| forecast.run(conn, format)),
+            Command::Calendar(calendar) => db::read(&mut conn, |conn| calendar.run(conn, format)),
+            Command::Stats(stats) => db::read(&mut conn, |conn| stats.run(conn, format)),
+            Command::Show(show) => db::read(&mut conn, |conn| show.run(conn, format)),
+            Command::Export(export) => db::read(&mut conn, |conn| export.run(conn, format)),
+
+            // everything else might write.
+            Command::Add(add) => self.transaction(&mut conn, |conn| add.run(conn, format)),
+            Command::Edit(edit) => self.transaction(&mut conn, |conn| edit.run(conn, format)),
+            Command::Finish(finish) => {
+                // this might ask how it went, so do that before taking the
+                // write lock instead of holding it while we wait.
+                let bump = finish.bump(&conn, format)?;
+                self.transaction(&mut conn, |conn| finish.run(conn, format, &bump))
+            }
+            Command::Reschedule(reschedule) => {
+                // like finish, ask before taking the write lock.
+                let plan = reschedule.plan(&conn, format)?;
+                self.transaction(&mut conn, |conn| plan.apply(conn, format))
+            }
+            Command::Delete(delete) => self.transaction(&mut conn, |conn| delete.run(conn, format)),
+            Command::Archive(archive) => {
+                self.transaction(&mut conn, |conn| archive.run(conn, format))
+            }
+            Command::Unarchive(unarchive) => {
+                self.transaction(&mut conn, |conn| unarchive.run(conn, format))
+            }
+            Command::Retire(retire) => {
+                // like finish, ask before taking the write lock.
+                let chosen = retire.choose(&conn, format)?;
+                self.transaction(&mut conn, |conn| retire.run(conn, format, chosen.as_ref()))
+            }
+            Command::Pause(pause) => self.transaction(&mut conn, |conn| pause.run(conn, format)),
+            Command::Vacation(vacation) => {
+                self.transaction(&mut conn, |conn| vacation.run(conn, format))
+            }
+            Command::Trash(trash) => self.transaction(&mut conn, |conn| trash.run(conn, format)),
+            Command::Import(import) => {
+                self.transaction(&mut conn, |conn| import.run(conn, format, self.dry_run))
+            }
+        }?;
+
+        if self.dry_run && format == Format::Human {
+            println!("\n(This was a dry run, so nothing was saved.)");
+        }
+
+        Ok(())
+    }
+
+    fn check_dry_run(&self, conn: &Connection) -> Result<()> {
+        if matches!(
+            self.command,
+            Command::Backup(_)
+                | Command::Restore(_)
+                | Command::Serve(_)
+                | Command::Rpc(_)
+                | Command::Review(_)
+        ) {
+            anyhow::bail!("--dry-run only works with commands that finish in a single step")
+        }
+
+        // migrations can't be rolled back like everything else, so a dry
+        // run can't be the thing that applies them.
+        if db::schema_version(conn)?.is_none_or(|version| version < db::latest_version()) {
+            anyhow::bail!("the database needs to be upgraded before --dry-run can use it. Run any command without --dry-run (like `tempo all`) to upgrade it")
+        }
+
+        Ok(())
+    }
+
+    /// Run a command in a single transaction, so that a failure partway
+    /// through (say, after creating a tag but before saving the item that
+    /// uses it) doesn't leave the store half-updated. With --dry-run, the
+    /// transaction always rolls back instead.
+    fn transaction<F>(&self, conn: &mut Connection, work: F) -> Result<()>
+    where
+        F: FnOnce(&Connection) -> Result<()>,
+    {
+        if self.dry_run {
+            db::dry_run(conn, work)
+        } else {
+            db::transaction(conn, work)
         }
     }
 
@@ -96,7 +284,7 @@ impl Opts {
         }
 
         log::info!("using \"{}\" as the path to the database", path.display());
-        Connection::open(path).context("couldn't open the database")
+        db::open(&path)
     }
 
     fn get_db_path(&self) -> Result<PathBuf> {
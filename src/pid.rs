@@ -3,7 +3,7 @@ static INTEGRAL_FACTOR: f64 = 0.3;
 static INTEGRAL_DECAY: f64 = 0.5;
 static DERIVATIVE_FACTOR: f64 = 0.1;
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct Pid {
     pub integral: f64,
     pub last_error: f64,
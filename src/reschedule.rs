@@ -0,0 +1,184 @@
+use crate::cadence::Cadence;
+use crate::cli::parse_utc_datetime;
+use crate::date::Date;
+use crate::item::Item;
+use crate::status::Status;
+use anyhow::{bail, Context, Result};
+use core::str::FromStr;
+use rusqlite::Connection;
+
+/// Sort items by when they're due, then give them new `next` dates spread
+/// evenly over `over` starting at `start`, so they don't all land at once.
+pub fn spread(items: &mut [Item], start: Date, over: Cadence) {
+    items.sort_by(|a, b| {
+        a.next
+            .partial_cmp(&b.next)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.id.cmp(&b.id))
+    });
+
+    let count = i64::try_from(items.len()).unwrap_or(i64::MAX);
+    let days = over.days.max(1);
+
+    for (index, item) in (0..).zip(items.iter_mut()) {
+        item.next = start + Cadence::days(index * days / count);
+    }
+}
+
+/// A condition on items, like `next<2022-03-01` or `status=learning`.
+#[derive(Debug, PartialEq)]
+pub enum Filter {
+    Next(Op, Date),
+    Cadence(Op, Cadence),
+    Status(Status),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Op {
+    fn compare<T: PartialOrd>(self, left: &T, right: &T) -> bool {
+        match self {
+            Op::Less => left < right,
+            Op::LessOrEqual => left <= right,
+            Op::Equal => left == right,
+            Op::GreaterOrEqual => left >= right,
+            Op::Greater => left > right,
+        }
+    }
+}
+
+impl Filter {
+    pub fn matches(&self, item: &Item, conn: &Connection) -> Result<bool> {
+        Ok(match self {
+            Filter::Next(op, date) => op.compare(&item.next, date),
+            Filter::Cadence(op, cadence) => op.compare(&item.cadence, cadence),
+            Filter::Status(status) => Status::of(item, conn)? == *status,
+        })
+    }
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        // check two-character operators first so `<=` isn't read as `<`
+        let (field, op, value) = [
+            ("<=", Op::LessOrEqual),
+            (">=", Op::GreaterOrEqual),
+            ("<", Op::Less),
+            (">", Op::Greater),
+            ("=", Op::Equal),
+        ]
+        .iter()
+        .find_map(|(symbol, op)| {
+            input
+                .split_once(symbol)
+                .map(|(field, value)| (field.trim(), *op, value.trim()))
+        })
+        .context("expected a comparison like next<2022-03-01, cadence>=2w, or status=learning")?;
+
+        match (field, op) {
+            ("next", _) => Ok(Filter::Next(op, parse_utc_datetime(value)?)),
+            ("cadence", _) => Ok(Filter::Cadence(
+                op,
+                Cadence::from_str(value)
+                    .with_context(|| format!("couldn't parse cadence \"{value}\""))?,
+            )),
+            ("status", Op::Equal) => match value {
+                "learning" => Ok(Filter::Status(Status::Learning)),
+                "settled" => Ok(Filter::Status(Status::Settled)),
+                _ => bail!("status can be \"learning\" or \"settled\", not \"{value}\""),
+            },
+            ("status", _) => bail!("status can only be compared with ="),
+            _ => bail!("can't filter on \"{field}\". Try next, cadence, or status"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pid::Pid;
+
+    fn item(id: u64, cadence: i64, next: Date) -> Item {
+        Item {
+            id,
+            text: format!("item {id}"),
+            tag_id: None,
+            cadence: Cadence::days(cadence),
+            next,
+            pid: Pid::default(),
+            archived: false,
+            paused_until: None,
+            learning_steps: 0,
+        }
+    }
+
+    #[test]
+    fn spreads_in_order() {
+        let mut items = vec![
+            item(1, 1, Date::ymd(2022, 1, 9)),
+            item(2, 1, Date::ymd(2022, 1, 1)),
+            item(3, 1, Date::ymd(2022, 1, 5)),
+        ];
+
+        spread(&mut items, Date::ymd(2022, 2, 1), Cadence::days(6));
+
+        let moved: Vec<(u64, Date)> = items.iter().map(|item| (item.id, item.next)).collect();
+        assert_eq!(
+            vec![
+                (2, Date::ymd(2022, 2, 1)),
+                (3, Date::ymd(2022, 2, 3)),
+                (1, Date::ymd(2022, 2, 5)),
+            ],
+            moved
+        );
+    }
+
+    #[test]
+    fn parses_filters() {
+        assert_eq!(
+            Filter::Next(Op::LessOrEqual, Date::ymd(2022, 3, 1)),
+            "next<=2022-03-01".parse::<Filter>().unwrap()
+        );
+        assert_eq!(
+            Filter::Cadence(Op::Greater, Cadence::weeks(2)),
+            "cadence > 2w".parse::<Filter>().unwrap()
+        );
+        assert_eq!(
+            Filter::Status(Status::Settled),
+            "status=settled".parse::<Filter>().unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_bad_filters() {
+        assert!("next".parse::<Filter>().is_err());
+        assert!("text=hello".parse::<Filter>().is_err());
+        assert!("status>learning".parse::<Filter>().is_err());
+        assert!("cadence<soon".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn matches_items() {
+        let conn = Connection::open_in_memory().unwrap();
+        let item = item(1, 7, Date::ymd(2022, 1, 5));
+
+        assert!(Filter::Next(Op::Less, Date::ymd(2022, 1, 6))
+            .matches(&item, &conn)
+            .unwrap());
+        assert!(!Filter::Cadence(Op::Greater, Cadence::weeks(1))
+            .matches(&item, &conn)
+            .unwrap());
+        assert!(Filter::Cadence(Op::Equal, Cadence::weeks(1))
+            .matches(&item, &conn)
+            .unwrap());
+    }
+}
@@ -0,0 +1,175 @@
+use crate::cadence::Cadence;
+use crate::history::Finish;
+use crate::item::{Bump, Item};
+use anyhow::{Context, Result};
+use clap::Parser;
+use rusqlite::Connection;
+
+/// When an item has been pushed away enough that it's probably time to
+/// stop seeing it.
+#[derive(Debug, Parser)]
+pub struct Policy {
+    /// Suggest retiring items that got this many "much-later" responses in
+    /// a row
+    #[clap(long, env = "TEMPO_RETIRE_AFTER", default_value_t = 3)]
+    pub after_much_later: usize,
+
+    /// Suggest retiring items whose cadence has grown longer than this.
+    /// Supports the same units as `add --cadence`.
+    #[clap(long, env = "TEMPO_RETIRE_LONGER_THAN", default_value = "2y")]
+    pub longer_than: Cadence,
+}
+
+/// Why an item should be retired.
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Reason {
+    MuchLater { streak: usize },
+    LongCadence { cadence: Cadence },
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Candidate {
+    pub item: Item,
+
+    #[serde(flatten)]
+    pub reason: Reason,
+}
+
+impl Policy {
+    /// Get the policy from the environment (or the defaults), the same way
+    /// `tempo retire` would if it weren't given any flags.
+    pub fn from_env() -> Result<Policy> {
+        Policy::try_parse_from(["retire"]).context("couldn't read the retirement policy")
+    }
+
+    /// Check whether an item should be retired. Archived items are already
+    /// retired, so they never are.
+    pub fn check(&self, item: &Item, conn: &Connection) -> Result<Option<Reason>> {
+        if item.archived {
+            return Ok(None);
+        }
+
+        if self.after_much_later > 0 {
+            let recent: Vec<Finish> =
+                Finish::recent(conn, item.id, self.after_much_later)?.collect();
+
+            if recent.len() == self.after_much_later
+                && recent.iter().all(|finish| finish.bump == Bump::MuchLater)
+            {
+                return Ok(Some(Reason::MuchLater {
+                    streak: self.after_much_later,
+                }));
+            }
+        }
+
+        if item.cadence > self.longer_than {
+            return Ok(Some(Reason::LongCadence {
+                cadence: item.cadence,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    pub fn candidates(&self, conn: &Connection) -> Result<Vec<Candidate>> {
+        let mut candidates = Vec::new();
+
+        for item in Item::all(conn).context("couldn't get items from the database")? {
+            if let Some(reason) = self.check(&item, conn)? {
+                candidates.push(Candidate { item, reason });
+            }
+        }
+
+        Ok(candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Date;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn
+    }
+
+    fn insert(conn: &Connection, cadence: Cadence) -> Item {
+        let id = conn
+            .query_row(
+                "INSERT INTO items (text, cadence, next) VALUES ('test', ?, ?) RETURNING id",
+                params![cadence, Date::ymd(2022, 1, 1)],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        Item::get(id, conn).unwrap()
+    }
+
+    fn policy() -> Policy {
+        Policy::try_parse_from(["retire", "--after-much-later", "2", "--longer-than", "1y"])
+            .unwrap()
+    }
+
+    #[test]
+    fn much_later_streak() {
+        let conn = conn();
+        let item = insert(&conn, Cadence::days(7));
+
+        Finish::record(&conn, &item, &Bump::MuchLater, item.next).unwrap();
+        assert_eq!(None, policy().check(&item, &conn).unwrap());
+
+        Finish::record(&conn, &item, &Bump::MuchLater, item.next).unwrap();
+        assert_eq!(
+            Some(Reason::MuchLater { streak: 2 }),
+            policy().check(&item, &conn).unwrap()
+        );
+
+        Finish::record(&conn, &item, &Bump::JustRight, item.next).unwrap();
+        assert_eq!(None, policy().check(&item, &conn).unwrap());
+    }
+
+    #[test]
+    fn long_cadence() {
+        let conn = conn();
+        let item = insert(&conn, Cadence::days(400));
+
+        assert_eq!(
+            Some(Reason::LongCadence {
+                cadence: Cadence::days(400)
+            }),
+            policy().check(&item, &conn).unwrap()
+        );
+    }
+
+    #[test]
+    fn skips_archived_items() {
+        let conn = conn();
+        let mut item = insert(&conn, Cadence::days(400));
+        item.archived = true;
+
+        assert_eq!(None, policy().check(&item, &conn).unwrap());
+    }
+
+    #[test]
+    fn finds_candidates() {
+        let conn = conn();
+        insert(&conn, Cadence::days(7));
+        insert(&conn, Cadence::days(400));
+
+        let ids: Vec<u64> = policy()
+            .candidates(&conn)
+            .unwrap()
+            .iter()
+            .map(|candidate| candidate.item.id)
+            .collect();
+
+        assert_eq!(vec![2], ids);
+    }
+}
@@ -0,0 +1,507 @@
+use crate::api::{self, EditParams, SnoozeParams};
+use crate::date::Date;
+use crate::db;
+use crate::item::{Bump, Item};
+use crate::tag::Tag;
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use std::io::Write;
+
+/// A key press in a review session. These are separate from the terminal's
+/// key events so that a session can be driven by a script (or by tests.)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Escape,
+}
+
+/// Something that happened to an item during a review.
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Finished {
+        id: u64,
+        text: String,
+        #[serde(with = "crate::date::ymd")]
+        next: Date,
+    },
+    Snoozed {
+        id: u64,
+        text: String,
+        #[serde(with = "crate::date::ymd")]
+        until: Date,
+    },
+    Edited {
+        id: u64,
+        text: String,
+    },
+    Skipped {
+        id: u64,
+        text: String,
+    },
+}
+
+static BUMPS: [(char, Bump, &str); 5] = [
+    ('1', Bump::MuchEarlier, "much earlier"),
+    ('2', Bump::Earlier, "earlier"),
+    ('3', Bump::JustRight, "just right"),
+    ('4', Bump::Later, "later"),
+    ('5', Bump::MuchLater, "much later"),
+];
+
+static DEFAULT_SNOOZE: &str = "1d";
+
+/// Walk through `items` one at a time, acting on each key press until the
+/// items or keys run out (or the user quits.) Every change is saved as it
+/// happens, so quitting partway through doesn't lose anything.
+pub fn run(
+    conn: &mut Connection,
+    items: Vec<Item>,
+    keys: &mut impl Iterator<Item = Key>,
+    out: &mut impl Write,
+    open: &mut impl FnMut(&str) -> Result<()>,
+) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    let total = items.len();
+
+    'items: for (index, mut item) in items.into_iter().enumerate() {
+        show(conn, &item, index, total, out)?;
+
+        while let Some(key) = keys.next() {
+            match key {
+                Key::Char('q') | Key::Escape => break 'items,
+
+                Key::Char('n') => {
+                    changes.push(Change::Skipped {
+                        id: item.id,
+                        text: item.text.clone(),
+                    });
+                    continue 'items;
+                }
+
+                Key::Char(c) if BUMPS.iter().any(|(key, _, _)| *key == c) => {
+                    if let Some(change) = finish(conn, &item, c, out)? {
+                        changes.push(change);
+                        continue 'items;
+                    }
+                }
+
+                Key::Char('s') => {
+                    if let Some(change) = snooze(conn, &item, keys, out)? {
+                        changes.push(change);
+                        continue 'items;
+                    }
+                }
+
+                Key::Char('e') => {
+                    if let Some(edited) = edit(conn, &item, keys, out)? {
+                        changes.push(Change::Edited {
+                            id: edited.id,
+                            text: edited.text.clone(),
+                        });
+                        item = edited;
+                        show(conn, &item, index, total, out)?;
+                    }
+                }
+
+                Key::Char('o') => match find_url(&item.text) {
+                    Some(url) => {
+                        if let Err(err) = open(url) {
+                            line(out, &format!("Couldn't open {url}: {err:#}"))?;
+                        }
+                    }
+                    None => line(out, "There's no link in this item to open")?,
+                },
+
+                _ => line(out, "I don't know that key. Press q to quit")?,
+            }
+        }
+
+        // the keys ran out
+        break;
+    }
+
+    summarize(&changes, out)?;
+
+    Ok(changes)
+}
+
+fn finish(
+    conn: &mut Connection,
+    item: &Item,
+    key: char,
+    out: &mut impl Write,
+) -> Result<Option<Change>> {
+    let (_, bump, _) = BUMPS
+        .iter()
+        .find(|(bump_key, _, _)| *bump_key == key)
+        .context("that key isn't a bump")?;
+
+    match db::transaction(conn, |conn| Ok(api::finish(conn, item.id, bump)?)) {
+        Ok(finished) => {
+            line(out, &format!("Finished! Next time is {}", finished.next))?;
+
+            Ok(Some(Change::Finished {
+                id: finished.id,
+                text: finished.text,
+                next: finished.next,
+            }))
+        }
+        Err(err) => {
+            line(out, &format!("Couldn't finish this: {err:#}"))?;
+
+            Ok(None)
+        }
+    }
+}
+
+fn snooze(
+    conn: &mut Connection,
+    item: &Item,
+    keys: &mut impl Iterator<Item = Key>,
+    out: &mut impl Write,
+) -> Result<Option<Change>> {
+    let Some(until) = prompt(
+        &format!("Snooze until (a date, or a cadence like 2d) [{DEFAULT_SNOOZE}]: "),
+        keys,
+        out,
+    )?
+    else {
+        line(out, "Cancelled")?;
+        return Ok(None);
+    };
+
+    let params = SnoozeParams {
+        until: if until.is_empty() {
+            DEFAULT_SNOOZE.to_string()
+        } else {
+            until
+        },
+    };
+
+    match db::transaction(conn, |conn| Ok(api::snooze(conn, item.id, &params)?)) {
+        Ok(snoozed) => {
+            let until = snoozed.paused_until.context("snoozing didn't set a date")?;
+            line(out, &format!("Snoozed until {until}"))?;
+
+            Ok(Some(Change::Snoozed {
+                id: snoozed.id,
+                text: snoozed.text,
+                until,
+            }))
+        }
+        Err(err) => {
+            line(out, &format!("Couldn't snooze this: {err:#}"))?;
+
+            Ok(None)
+        }
+    }
+}
+
+fn edit(
+    conn: &mut Connection,
+    item: &Item,
+    keys: &mut impl Iterator<Item = Key>,
+    out: &mut impl Write,
+) -> Result<Option<Item>> {
+    let Some(text) = prompt("New text: ", keys, out)?.filter(|text| !text.is_empty()) else {
+        line(out, "Cancelled")?;
+        return Ok(None);
+    };
+
+    let params = EditParams {
+        text: Some(text),
+        tag: None,
+        next: None,
+        cadence: None,
+        bump: None,
+    };
+
+    match db::transaction(conn, |conn| Ok(api::edit(conn, item.id, &params)?)) {
+        Ok(edited) => Ok(Some(edited)),
+        Err(err) => {
+            line(out, &format!("Couldn't edit this: {err:#}"))?;
+
+            Ok(None)
+        }
+    }
+}
+
+fn show(
+    conn: &Connection,
+    item: &Item,
+    index: usize,
+    total: usize,
+    out: &mut impl Write,
+) -> Result<()> {
+    let tag = match item.tag_id {
+        Some(id) => Tag::get(conn, id)?.name,
+        None => "no tag".to_string(),
+    };
+
+    line(out, "")?;
+    line(out, &format!("[{}/{}] {}", index + 1, total, item.text))?;
+    line(
+        out,
+        &format!("      {} · every {} · due {}", tag, item.cadence, item.next),
+    )?;
+    line(out, "")?;
+    line(
+        out,
+        &format!(
+            "  {}",
+            BUMPS
+                .iter()
+                .map(|(key, _, name)| format!("{key} {name}"))
+                .collect::<Vec<String>>()
+                .join("  ")
+        ),
+    )?;
+    line(out, "  s snooze  e edit  o open  n skip  q quit")
+}
+
+fn summarize(changes: &[Change], out: &mut impl Write) -> Result<()> {
+    line(out, "")?;
+
+    if changes.is_empty() {
+        return line(out, "Nothing changed");
+    }
+
+    for change in changes {
+        line(
+            out,
+            &match change {
+                Change::Finished { id, text, next } => {
+                    format!("finished {id}: {text} (next on {next})")
+                }
+                Change::Snoozed { id, text, until } => {
+                    format!("snoozed {id}: {text} (until {until})")
+                }
+                Change::Edited { id, text } => format!("edited {id}: {text}"),
+                Change::Skipped { id, text } => format!("skipped {id}: {text}"),
+            },
+        )?;
+    }
+
+    let count = |want: fn(&Change) -> bool| changes.iter().filter(|change| want(change)).count();
+    line(
+        out,
+        &format!(
+            "Finished {}, snoozed {}, edited {}, and skipped {}",
+            count(|change| matches!(change, Change::Finished { .. })),
+            count(|change| matches!(change, Change::Snoozed { .. })),
+            count(|change| matches!(change, Change::Edited { .. })),
+            count(|change| matches!(change, Change::Skipped { .. })),
+        ),
+    )
+}
+
+/// Read a line of input, echoing it as it's typed. Returns `None` if the
+/// prompt was cancelled with escape (or the keys ran out.)
+fn prompt(
+    question: &str,
+    keys: &mut impl Iterator<Item = Key>,
+    out: &mut impl Write,
+) -> Result<Option<String>> {
+    let mut answer = String::new();
+    write!(out, "{question}").context("could not write to the terminal")?;
+    out.flush().context("could not flush the terminal")?;
+
+    for key in keys {
+        match key {
+            Key::Enter => {
+                line(out, "")?;
+                return Ok(Some(answer.trim().to_string()));
+            }
+            Key::Escape => break,
+            Key::Backspace => {
+                if answer.pop().is_some() {
+                    write!(out, "\u{8} \u{8}").context("could not write to the terminal")?;
+                }
+            }
+            Key::Char(c) => {
+                answer.push(c);
+                write!(out, "{c}").context("could not write to the terminal")?;
+            }
+        }
+        out.flush().context("could not flush the terminal")?;
+    }
+
+    line(out, "")?;
+    Ok(None)
+}
+
+/// Write a line ending in `\r\n`, since the terminal is in raw mode and
+/// won't move back to the start of the line on its own.
+fn line(out: &mut impl Write, text: &str) -> Result<()> {
+    write!(out, "{text}\r\n").context("could not write to the terminal")
+}
+
+fn find_url(text: &str) -> Option<&str> {
+    text.split_whitespace()
+        .find(|word| word.starts_with("http://") || word.starts_with("https://"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cadence::Cadence;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        for text in ["first https://example.com", "second", "third"] {
+            conn.execute(
+                "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+                params![text, Cadence::days(1), Date::ymd(2022, 1, 1)],
+            )
+            .unwrap();
+        }
+
+        conn
+    }
+
+    fn keys(script: &str) -> impl Iterator<Item = Key> + '_ {
+        script.chars().map(|c| match c {
+            '\n' => Key::Enter,
+            '\u{1b}' => Key::Escape,
+            '\u{8}' => Key::Backspace,
+            c => Key::Char(c),
+        })
+    }
+
+    /// Review everything that's due with a script of key presses, returning
+    /// the changes, what was printed, and any URLs that were opened.
+    fn review(conn: &mut Connection, script: &str) -> (Vec<Change>, String, Vec<String>) {
+        let items = Item::due(conn, false).unwrap().collect();
+        let mut out = Vec::new();
+        let mut opened = Vec::new();
+
+        let changes = run(
+            conn,
+            items,
+            &mut keys(script),
+            &mut out,
+            &mut |url: &str| {
+                opened.push(url.to_string());
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        (changes, String::from_utf8(out).unwrap(), opened)
+    }
+
+    #[test]
+    fn bump_keys_finish_items() {
+        let mut conn = conn();
+        let (changes, _, _) = review(&mut conn, "35");
+
+        assert!(matches!(changes[0], Change::Finished { id: 1, .. }));
+        assert!(matches!(changes[1], Change::Finished { id: 2, .. }));
+        assert!(Item::get(1, &conn).unwrap().next > Date::ymd(2022, 1, 1));
+        assert!(Item::get(2, &conn).unwrap().pid.last_error > 0.0);
+        assert_eq!(Date::ymd(2022, 1, 1), Item::get(3, &conn).unwrap().next);
+    }
+
+    #[test]
+    fn snooze_with_default() {
+        let mut conn = conn();
+        let (changes, _, _) = review(&mut conn, "s\nq");
+
+        assert_eq!(
+            vec![Change::Snoozed {
+                id: 1,
+                text: "first https://example.com".into(),
+                until: Date::today() + Cadence::days(1),
+            }],
+            changes
+        );
+    }
+
+    #[test]
+    fn snooze_with_cadence() {
+        let mut conn = conn();
+        review(&mut conn, "s2w\nq");
+
+        assert_eq!(
+            Some(Date::today() + Cadence::weeks(2)),
+            Item::get(1, &conn).unwrap().paused_until
+        );
+    }
+
+    #[test]
+    fn cancelled_snooze_stays_on_the_item() {
+        let mut conn = conn();
+        let (changes, _, _) = review(&mut conn, "s2w\u{1b}3q");
+
+        assert!(matches!(changes[..], [Change::Finished { id: 1, .. }]));
+        assert_eq!(None, Item::get(1, &conn).unwrap().paused_until);
+    }
+
+    #[test]
+    fn edit_then_finish() {
+        let mut conn = conn();
+        let (changes, out, _) = review(&mut conn, "nenew texx\u{8}t\n3q");
+
+        assert_eq!(
+            Change::Edited {
+                id: 2,
+                text: "new text".into()
+            },
+            changes[1]
+        );
+        assert!(matches!(changes[2], Change::Finished { id: 2, .. }));
+        assert!(out.contains("[2/3] new text"));
+    }
+
+    #[test]
+    fn open_link() {
+        let mut conn = conn();
+        let (changes, _, opened) = review(&mut conn, "onoq");
+
+        assert_eq!(vec!["https://example.com"], opened);
+        assert!(matches!(changes[..], [Change::Skipped { id: 1, .. }]));
+    }
+
+    #[test]
+    fn skip_changes_nothing() {
+        let mut conn = conn();
+        let (changes, _, _) = review(&mut conn, "nnn");
+
+        assert_eq!(3, changes.len());
+        assert_eq!(3, Item::due(&conn, false).unwrap().count());
+    }
+
+    #[test]
+    fn running_out_of_keys_stops() {
+        let mut conn = conn();
+        let (changes, out, _) = review(&mut conn, "3");
+
+        assert_eq!(1, changes.len());
+        assert!(out.contains("[2/3] second"));
+        assert!(!out.contains("[3/3] third"));
+    }
+
+    #[test]
+    fn summary() {
+        let mut conn = conn();
+        let (_, out, _) = review(&mut conn, "3ns\n");
+
+        assert!(out.contains("Finished 1, snoozed 1, edited 0, and skipped 1"));
+    }
+
+    #[test]
+    fn nothing_changed() {
+        let mut conn = conn();
+        let (_, out, _) = review(&mut conn, "q");
+
+        assert!(out.ends_with("Nothing changed\r\n"));
+    }
+}
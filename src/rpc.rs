@@ -0,0 +1,570 @@
+use crate::api;
+use crate::cli::{all, ready};
+use crate::db;
+use crate::item::FinishError;
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use std::io::{BufRead, Write};
+use thiserror::Error;
+
+/// What went wrong with a call. The first five are defined by the JSON-RPC
+/// spec, and the rest are ours (in the range the spec sets aside for
+/// servers.) Clients should match on the number or on `data.kind` instead
+/// of the message, which is meant for people.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Code {
+    ParseError,
+    InvalidRequest,
+    MethodNotFound,
+    InvalidParams,
+    Internal,
+    NotFound,
+    NotDue,
+    Duplicate,
+    Conflict,
+}
+
+impl Code {
+    fn number(self) -> i64 {
+        match self {
+            Code::ParseError => -32700,
+            Code::InvalidRequest => -32600,
+            Code::MethodNotFound => -32601,
+            Code::InvalidParams => -32602,
+            Code::Internal => -32603,
+            Code::NotFound => -32001,
+            Code::NotDue => -32002,
+            Code::Duplicate => -32003,
+            Code::Conflict => -32004,
+        }
+    }
+
+    fn kind(self) -> &'static str {
+        match self {
+            Code::ParseError => "parse_error",
+            Code::InvalidRequest => "invalid_request",
+            Code::MethodNotFound => "method_not_found",
+            Code::InvalidParams => "invalid_params",
+            Code::Internal => "internal",
+            Code::NotFound => "not_found",
+            Code::NotDue => "not_due",
+            Code::Duplicate => "duplicate",
+            Code::Conflict => "conflict",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub struct Error {
+    code: Code,
+    message: String,
+    data: Map<String, Value>,
+}
+
+impl Error {
+    fn new(code: Code, message: impl Into<String>) -> Error {
+        Error {
+            code,
+            message: message.into(),
+            data: Map::new(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut data = self.data.clone();
+        data.insert("kind".into(), self.code.kind().into());
+
+        json!({
+            "code": self.code.number(),
+            "message": self.message,
+            "data": data,
+        })
+    }
+}
+
+impl From<api::Error> for Error {
+    fn from(err: api::Error) -> Error {
+        let code = match &err {
+            api::Error::NotFound(_) => Code::NotFound,
+            api::Error::InvalidParams(_) => Code::InvalidParams,
+            api::Error::Duplicate(_) => Code::Duplicate,
+            api::Error::Finish(FinishError::NotDue(_)) => Code::NotDue,
+            api::Error::Finish(_) | api::Error::Conflict(_) => Code::Conflict,
+            api::Error::Internal(_) => Code::Internal,
+        };
+
+        let mut rpc = Error::new(code, format!("{err:#}"));
+
+        // same as `tempo add --format json` when it finds a duplicate
+        if let api::Error::Duplicate(existing) = &err {
+            if let Ok(existing) = serde_json::to_value(existing) {
+                rpc.data.insert("existing".into(), existing);
+            }
+        }
+
+        rpc
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Error {
+        Error::new(Code::Internal, format!("{err:#}"))
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReadyParams {
+    tag: Option<Vec<String>>,
+    limit: Option<usize>,
+    #[serde(default)]
+    include_archived: bool,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AllParams {
+    tag: Option<String>,
+    #[serde(default)]
+    include_archived: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Empty {}
+
+/// Read JSON-RPC 2.0 messages from `input`, one per line, and write a
+/// response for each to `output` until `input` runs out. Each call runs in
+/// its own transaction.
+pub fn serve(conn: &mut Connection, input: impl BufRead, mut output: impl Write) -> Result<()> {
+    for line in input.lines() {
+        let line = line.context("could not read from stdin")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(response) = handle_line(conn, &line) {
+            writeln!(output, "{response}").context("could not write a response")?;
+            output.flush().context("could not flush the response")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_line(conn: &mut Connection, line: &str) -> Option<Value> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(err) => {
+            return Some(response(
+                &Value::Null,
+                Err(Error::new(
+                    Code::ParseError,
+                    format!("couldn't parse JSON: {err}"),
+                )),
+            ))
+        }
+    };
+
+    match message {
+        Value::Array(batch) if batch.is_empty() => Some(response(
+            &Value::Null,
+            Err(Error::new(Code::InvalidRequest, "a batch can't be empty")),
+        )),
+
+        Value::Array(batch) => {
+            let responses: Vec<Value> = batch
+                .into_iter()
+                .filter_map(|message| handle_message(conn, message))
+                .collect();
+
+            // a batch of only notifications gets no response at all
+            if responses.is_empty() {
+                None
+            } else {
+                Some(Value::Array(responses))
+            }
+        }
+
+        message => handle_message(conn, message),
+    }
+}
+
+fn handle_message(conn: &mut Connection, message: Value) -> Option<Value> {
+    // requests without an ID are notifications, which don't get a response
+    // (even if they fail.)
+    let id = message.get("id").cloned();
+
+    let request = match serde_json::from_value::<Request>(message) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => {
+            return Some(response(
+                &id.unwrap_or_default(),
+                Err(Error::new(Code::InvalidRequest, "jsonrpc must be \"2.0\"")),
+            ))
+        }
+        Err(err) => {
+            return Some(response(
+                &id.unwrap_or_default(),
+                Err(Error::new(
+                    Code::InvalidRequest,
+                    format!("this isn't a valid request: {err}"),
+                )),
+            ))
+        }
+    };
+
+    let work = |conn: &Connection| Ok(call(conn, &request.method, request.params.clone())?);
+    let result = if matches!(request.method.as_str(), "ready" | "all") {
+        db::read(conn, work)
+    } else {
+        db::transaction(conn, work)
+    }
+    .map_err(|err| err.downcast::<Error>().unwrap_or_else(Error::from));
+
+    id.map(|id| response(&id, result))
+}
+
+fn response(id: &Value, result: Result<Value, Error>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(err) => json!({ "jsonrpc": "2.0", "error": err.to_json(), "id": id }),
+    }
+}
+
+fn call(conn: &Connection, method: &str, params: Value) -> Result<Value, Error> {
+    match method {
+        "ready" => {
+            let params: ReadyParams = parse_params(params)?;
+            to_json(&ready::query(
+                conn,
+                params.tag.as_deref(),
+                params.limit,
+                params.include_archived,
+            )?)
+        }
+
+        "all" => {
+            let params: AllParams = parse_params(params)?;
+            to_json(&all::query(
+                conn,
+                params.tag.as_deref(),
+                params.include_archived,
+            )?)
+        }
+
+        "add" => to_json(&api::add(conn, &parse_params(params)?)?),
+
+        "edit" => {
+            let (id, params) = parse_params_with_id(params)?;
+            to_json(&api::edit(conn, id, &params)?)
+        }
+
+        "finish" => {
+            let (id, params): (u64, api::FinishParams) = parse_params_with_id(params)?;
+            to_json(&api::finish(conn, id, &params.bump()?)?)
+        }
+
+        "delete" => {
+            let (id, Empty {}) = parse_params_with_id(params)?;
+            to_json(&api::delete(conn, id)?)
+        }
+
+        _ => Err(Error::new(
+            Code::MethodNotFound,
+            format!("there's no method called \"{method}\""),
+        )),
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<Value, Error> {
+    serde_json::to_value(value)
+        .context("could not convert the result to JSON")
+        .map_err(Error::from)
+}
+
+/// Parse `params` by name. Missing params are treated as empty, so methods
+/// where everything is optional can be called without any.
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, Error> {
+    let params = if params.is_null() { json!({}) } else { params };
+
+    serde_json::from_value(params)
+        .map_err(|err| Error::new(Code::InvalidParams, format!("invalid params: {err}")))
+}
+
+/// Parse params for a method that works on a single item, which has to
+/// have an `id`.
+fn parse_params_with_id<T: DeserializeOwned>(params: Value) -> Result<(u64, T), Error> {
+    let Value::Object(mut params) = params else {
+        return Err(Error::new(
+            Code::InvalidParams,
+            "params must be an object with an item ID",
+        ));
+    };
+
+    let id = params
+        .remove("id")
+        .and_then(|id| id.as_u64())
+        .ok_or_else(|| Error::new(Code::InvalidParams, "params need an item ID in \"id\""))?;
+
+    Ok((id, parse_params(Value::Object(params))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cadence::Cadence;
+    use crate::date::Date;
+    use crate::item::Item;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params!["due", Cadence::days(1), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params![
+                "not due",
+                Cadence::days(1),
+                Date::today() + Cadence::weeks(1)
+            ],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    /// Send some lines and get back the parsed responses.
+    fn send(conn: &mut Connection, input: &str) -> Vec<Value> {
+        let mut output = Vec::new();
+        serve(conn, input.as_bytes(), &mut output).unwrap();
+
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn call_one(conn: &mut Connection, method: &str, params: &Value) -> Value {
+        let request = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 });
+
+        send(conn, &request.to_string()).remove(0)
+    }
+
+    #[test]
+    fn ready() {
+        let response = call_one(&mut conn(), "ready", &Value::Null);
+
+        assert_eq!(1, response["id"]);
+        assert_eq!("due", response["result"][0]["text"]);
+        assert_eq!(1, response["result"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn all() {
+        let response = call_one(&mut conn(), "all", &json!({}));
+
+        assert_eq!(2, response["result"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn all_matches_all_json_output() {
+        let expected = serde_json::to_value(all::query(&conn(), None, false).unwrap()).unwrap();
+        let response = call_one(&mut conn(), "all", &json!({}));
+
+        assert_eq!(expected, response["result"]);
+        assert_eq!("learning", response["result"][0]["status"]);
+    }
+
+    #[test]
+    fn add_returns_the_same_shape_as_json_output() {
+        let mut conn = conn();
+        let response = call_one(&mut conn, "add", &json!({ "text": "new", "cadence": "1w" }));
+
+        let item = Item::get(3, &conn).unwrap();
+        assert_eq!(serde_json::to_value(&item).unwrap(), response["result"]);
+    }
+
+    #[test]
+    fn edit() {
+        let mut conn = conn();
+        call_one(&mut conn, "edit", &json!({ "id": 1, "text": "edited" }));
+
+        assert_eq!("edited", Item::get(1, &conn).unwrap().text);
+    }
+
+    #[test]
+    fn finish() {
+        let mut conn = conn();
+        let response = call_one(&mut conn, "finish", &json!({ "id": 1, "bump": "later" }));
+
+        assert_eq!(1, response["result"]["id"]);
+        assert!(Item::get(1, &conn).unwrap().next > Date::ymd(2022, 1, 1));
+    }
+
+    #[test]
+    fn delete() {
+        let mut conn = conn();
+        call_one(&mut conn, "delete", &json!({ "id": 1 }));
+
+        assert!(Item::get(1, &conn).is_err());
+    }
+
+    mod errors {
+        use super::*;
+
+        fn error(response: &Value) -> (i64, &str) {
+            (
+                response["error"]["code"].as_i64().unwrap(),
+                response["error"]["data"]["kind"].as_str().unwrap(),
+            )
+        }
+
+        #[test]
+        fn parse_error() {
+            let response = send(&mut conn(), "{not json").remove(0);
+
+            assert_eq!((-32700, "parse_error"), error(&response));
+            assert_eq!(Value::Null, response["id"]);
+        }
+
+        #[test]
+        fn invalid_request() {
+            let response =
+                send(&mut conn(), r#"{"jsonrpc":"1.0","method":"ready","id":1}"#).remove(0);
+
+            assert_eq!((-32600, "invalid_request"), error(&response));
+        }
+
+        #[test]
+        fn method_not_found() {
+            let response = call_one(&mut conn(), "explode", &Value::Null);
+
+            assert_eq!((-32601, "method_not_found"), error(&response));
+        }
+
+        #[test]
+        fn invalid_params() {
+            let response = call_one(
+                &mut conn(),
+                "finish",
+                &json!({ "id": 1, "bump": "sideways" }),
+            );
+
+            assert_eq!((-32602, "invalid_params"), error(&response));
+        }
+
+        #[test]
+        fn missing_id() {
+            let response = call_one(&mut conn(), "delete", &json!({}));
+
+            assert_eq!((-32602, "invalid_params"), error(&response));
+        }
+
+        #[test]
+        fn not_found() {
+            let response = call_one(&mut conn(), "finish", &json!({ "id": 99, "bump": "later" }));
+
+            assert_eq!((-32001, "not_found"), error(&response));
+        }
+
+        #[test]
+        fn not_due() {
+            let response = call_one(&mut conn(), "finish", &json!({ "id": 2, "bump": "later" }));
+
+            assert_eq!((-32002, "not_due"), error(&response));
+        }
+
+        #[test]
+        fn duplicate() {
+            let response = call_one(&mut conn(), "add", &json!({ "text": "Due" }));
+
+            assert_eq!((-32003, "duplicate"), error(&response));
+            assert_eq!(1, response["error"]["data"]["existing"]["id"]);
+        }
+
+        #[test]
+        fn failures_roll_back() {
+            let mut conn = conn();
+            call_one(
+                &mut conn,
+                "edit",
+                &json!({ "id": 1, "tag": "new tag", "bump": "sideways" }),
+            );
+
+            assert!(crate::tag::Tag::get_by_name(&conn, "new tag").is_err());
+        }
+    }
+
+    mod protocol {
+        use super::*;
+
+        #[test]
+        fn one_response_per_line() {
+            let responses = send(
+                &mut conn(),
+                "{\"jsonrpc\":\"2.0\",\"method\":\"ready\",\"id\":\"a\"}\n\n{\"jsonrpc\":\"2.0\",\"method\":\"all\",\"id\":\"b\"}\n",
+            );
+
+            assert_eq!(
+                vec!["a", "b"],
+                responses
+                    .iter()
+                    .map(|response| response["id"].as_str().unwrap())
+                    .collect::<Vec<&str>>()
+            );
+        }
+
+        #[test]
+        fn notifications_get_no_response() {
+            let mut conn = conn();
+            let responses = send(
+                &mut conn,
+                r#"{"jsonrpc":"2.0","method":"delete","params":{"id":1}}"#,
+            );
+
+            assert!(responses.is_empty());
+            assert!(Item::get(1, &conn).is_err());
+        }
+
+        #[test]
+        fn batches() {
+            let responses = send(
+                &mut conn(),
+                r#"[{"jsonrpc":"2.0","method":"ready","id":1},{"jsonrpc":"2.0","method":"all"},{"jsonrpc":"2.0","method":"nope","id":2}]"#,
+            );
+
+            let batch = responses[0].as_array().unwrap();
+            assert_eq!(2, batch.len());
+            assert_eq!(1, batch[0]["id"]);
+            assert_eq!(-32601, batch[1]["error"]["code"]);
+        }
+
+        #[test]
+        fn empty_batch() {
+            let response = send(&mut conn(), "[]").remove(0);
+
+            assert_eq!(-32600, response["error"]["code"]);
+        }
+    }
+}
@@ -0,0 +1,852 @@
+use crate::api;
+use crate::cadence::Cadence;
+use crate::cli::{all, ready};
+use crate::db;
+use crate::ics;
+use crate::tag::Tag;
+use anyhow::{anyhow, Context, Result};
+use core::fmt::Display;
+use core::str::FromStr;
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+use std::net::SocketAddr;
+use thiserror::Error;
+use tiny_http::{Header, Method};
+
+/// A small HTTP server for things that would rather subscribe to a URL than
+/// run a command (calendar apps, dashboards, phone shortcuts, etc.) Requests
+/// are handled one at a time, each in its own transaction.
+///
+/// Reading is open to anyone who can reach the server. Changing anything
+/// needs the token the server was started with, sent as `Authorization:
+/// Bearer <token>`. If there's no token, the server is read-only.
+pub struct Server {
+    http: tiny_http::Server,
+    token: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("couldn't find {0}")]
+    NotFound(String),
+    #[error("{0} doesn't support {1}")]
+    MethodNotAllowed(String, Method),
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("this needs a valid token in the Authorization header (like \"Bearer <token>\")")]
+    Unauthorized,
+    #[error("this server is read-only. Start it with --token to allow changes")]
+    ReadOnly,
+    #[error(transparent)]
+    Api(#[from] api::Error),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl Error {
+    fn status(&self) -> u16 {
+        match self {
+            Error::NotFound(_) | Error::Api(api::Error::NotFound(_)) => 404,
+            Error::MethodNotAllowed(..) => 405,
+            Error::BadRequest(_) | Error::Api(api::Error::InvalidParams(_)) => 400,
+            Error::Unauthorized => 401,
+            Error::ReadOnly => 403,
+            Error::Api(
+                api::Error::Duplicate(_) | api::Error::Finish(_) | api::Error::Conflict(_),
+            ) => 409,
+            Error::Api(api::Error::Internal(_)) | Error::Internal(_) => 500,
+        }
+    }
+}
+
+/// Everything we need from an HTTP request, read up front so that routing
+/// doesn't have to know about sockets.
+#[derive(Debug)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub token: Option<String>,
+    pub body: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn json<T: serde::Serialize>(value: &T) -> Result<Response> {
+        Ok(Response {
+            status: 200,
+            content_type: "application/json",
+            body: serde_json::to_string(value).context("could not convert the response to JSON")?,
+        })
+    }
+
+    fn error(err: &anyhow::Error) -> Response {
+        let known = err.downcast_ref::<Error>();
+        let body = match known {
+            Some(Error::Api(api)) => api.to_json(),
+            _ => serde_json::json!({ "error": format!("{err:#}") }),
+        };
+
+        Response {
+            status: known.map_or(500, Error::status),
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+}
+
+impl Server {
+    pub fn bind(addr: SocketAddr, token: Option<String>) -> Result<Server> {
+        let http = tiny_http::Server::http(addr)
+            .map_err(|err| anyhow!(err))
+            .with_context(|| format!("could not listen on {addr}"))?;
+
+        Ok(Server { http, token })
+    }
+
+    /// The address we're actually listening on, which is useful if we were
+    /// asked to bind to port 0.
+    pub fn address(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    /// Handle requests as they come in. This only returns if the server is
+    /// shut down from another thread (which the tests do to clean up.)
+    pub fn run(&self, conn: &mut Connection) {
+        for mut http_request in self.http.incoming_requests() {
+            let mut body = String::new();
+            let response = match http_request.as_reader().read_to_string(&mut body) {
+                Ok(_) => {
+                    let request = Request {
+                        method: http_request.method().clone(),
+                        url: http_request.url().to_string(),
+                        token: http_request
+                            .headers()
+                            .iter()
+                            .find(|header| header.field.equiv("Authorization"))
+                            .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+                            .map(str::to_string),
+                        body,
+                    };
+
+                    self.respond(conn, &request)
+                }
+                Err(err) => Response::error(
+                    &Error::BadRequest(format!("couldn't read the request body: {err}")).into(),
+                ),
+            };
+
+            log::info!(
+                "{} {} {}",
+                http_request.method(),
+                http_request.url(),
+                response.status
+            );
+
+            let http_response = tiny_http::Response::from_string(response.body)
+                .with_status_code(response.status)
+                .with_header(
+                    Header::from_bytes("Content-Type", response.content_type)
+                        .expect("content types are always valid headers"),
+                );
+
+            if let Err(err) = http_request.respond(http_response) {
+                log::warn!("could not send a response: {err}");
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub fn stop(&self) {
+        self.http.unblock();
+    }
+
+    fn respond(&self, conn: &mut Connection, request: &Request) -> Response {
+        // if anything goes wrong, the transaction rolls back, so a request
+        // either makes all of its changes or none of them. Reads don't take
+        // the write lock, so they never wait on a command that's writing.
+        let route = |conn: &Connection| Ok(self.route(conn, request)?);
+        let response = if request.method == Method::Get {
+            db::read(conn, route)
+        } else {
+            db::transaction(conn, route)
+        };
+
+        match response {
+            Ok(response) => response,
+            Err(err) => Response::error(&err),
+        }
+    }
+
+    fn route(&self, conn: &Connection, request: &Request) -> Result<Response, Error> {
+        let (path, query) = match request.url.split_once('?') {
+            Some((path, query)) => (path, Query::parse(query)),
+            None => (request.url.as_str(), Query::default()),
+        };
+
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        match (&request.method, segments.as_slice()) {
+            (Method::Get, ["ready.json"]) => {
+                let tags = query.all("tag");
+                check_tags(conn, &tags)?;
+                let items = ready::query(
+                    conn,
+                    if tags.is_empty() {
+                        None
+                    } else {
+                        Some(&tags[..])
+                    },
+                    query.get("limit")?,
+                    query.get("include_archived")?.unwrap_or(false),
+                )?;
+
+                Ok(Response::json(&items)?)
+            }
+
+            (Method::Get, ["items.json"]) => {
+                let tag = query.one("tag")?;
+                check_tags(conn, tag.as_slice())?;
+                let items = all::query(conn, tag, query.get("include_archived")?.unwrap_or(false))?;
+
+                Ok(Response::json(&items)?)
+            }
+
+            (Method::Get, ["calendar.ics"]) => {
+                let tags = query.all("tag");
+                check_tags(conn, &tags)?;
+                let calendar = ics::calendar(conn, &tags, query.get::<Cadence>("horizon")?)?;
+
+                Ok(Response {
+                    status: 200,
+                    content_type: "text/calendar; charset=utf-8",
+                    body: calendar,
+                })
+            }
+
+            (Method::Get, ["items", id]) => Ok(Response::json(&api::get(conn, item_id(id)?)?)?),
+
+            (Method::Post, ["items"]) => {
+                self.authorize(request)?;
+                let mut response = Response::json(&api::add(conn, &parse_body(&request.body)?)?)?;
+                response.status = 201;
+
+                Ok(response)
+            }
+
+            (Method::Patch, ["items", id]) => {
+                self.authorize(request)?;
+                let item = api::edit(conn, item_id(id)?, &parse_body(&request.body)?)?;
+
+                Ok(Response::json(&item)?)
+            }
+
+            (Method::Delete, ["items", id]) => {
+                self.authorize(request)?;
+
+                Ok(Response::json(&api::delete(conn, item_id(id)?)?)?)
+            }
+
+            (Method::Post, ["items", id, "finish"]) => {
+                self.authorize(request)?;
+                let params: api::FinishParams = parse_body(&request.body)?;
+                let item = api::finish(conn, item_id(id)?, &params.bump()?)?;
+
+                Ok(Response::json(&item)?)
+            }
+
+            (Method::Post, ["items", id, "snooze"]) => {
+                self.authorize(request)?;
+                let item = api::snooze(conn, item_id(id)?, &parse_body(&request.body)?)?;
+
+                Ok(Response::json(&item)?)
+            }
+
+            (
+                method,
+                ["ready.json" | "items.json" | "calendar.ics" | "items"]
+                | ["items", _]
+                | ["items", _, "finish" | "snooze"],
+            ) => Err(Error::MethodNotAllowed(path.to_string(), method.clone())),
+
+            _ => Err(Error::NotFound(path.to_string())),
+        }
+    }
+
+    fn authorize(&self, request: &Request) -> Result<(), Error> {
+        let expected = self.token.as_deref().ok_or(Error::ReadOnly)?;
+
+        match &request.token {
+            Some(given) if tokens_match(expected, given) => Ok(()),
+            _ => Err(Error::Unauthorized),
+        }
+    }
+}
+
+/// Compare tokens without bailing at the first difference, so response
+/// times don't leak how much of a guess was right.
+fn tokens_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Filtering on a tag that doesn't exist is more likely a typo than a
+/// request for nothing, so say so instead of returning an empty list.
+fn check_tags<T: AsRef<str>>(conn: &Connection, tags: &[T]) -> Result<(), Error> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    let known: Vec<String> = Tag::all(conn)?.map(|tag| tag.name).collect();
+    match tags
+        .iter()
+        .find(|tag| !known.iter().any(|name| name == tag.as_ref()))
+    {
+        Some(tag) => Err(Error::NotFound(format!("a tag named \"{}\"", tag.as_ref()))),
+        None => Ok(()),
+    }
+}
+
+/// IDs in paths that aren't numbers can't match any item.
+fn item_id(raw: &str) -> Result<u64, Error> {
+    raw.parse()
+        .map_err(|_| Error::NotFound(format!("item with ID {raw}")))
+}
+
+fn parse_body<T: DeserializeOwned>(body: &str) -> Result<T, Error> {
+    serde_json::from_str(body)
+        .map_err(|err| Error::BadRequest(format!("couldn't parse the request body: {err}")))
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Query(Vec<(String, String)>);
+
+impl Query {
+    fn parse(raw: &str) -> Query {
+        Query(
+            raw.split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| match pair.split_once('=') {
+                    Some((key, value)) => (decode(key), decode(value)),
+                    None => (decode(pair), String::new()),
+                })
+                .collect(),
+        )
+    }
+
+    fn all(&self, key: &str) -> Vec<String> {
+        self.0
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    fn one(&self, key: &str) -> Result<Option<&str>, Error> {
+        let mut values = self.0.iter().filter(|(k, _)| k == key);
+
+        match (values.next(), values.next()) {
+            (Some((_, value)), None) => Ok(Some(value)),
+            (None, _) => Ok(None),
+            (Some(_), Some(_)) => Err(Error::BadRequest(format!(
+                "expected at most one \"{key}\" parameter"
+            ))),
+        }
+    }
+
+    fn get<T>(&self, key: &str) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.one(key)? {
+            Some(raw) => T::from_str(raw).map(Some).map_err(|err| {
+                Error::BadRequest(format!("couldn't parse \"{key}\" from \"{raw}\": {err}"))
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Decode a percent-encoded query string component. Invalid escapes are
+/// left alone rather than rejected.
+fn decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                out.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Date;
+    use crate::item::Item;
+    use crate::tag::Tag;
+    use crate::trash::Trashed;
+    use rusqlite::params;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn.execute("INSERT INTO tags (id, name) VALUES (1, \"my tag\")", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO items (text, cadence, next, tag_id) VALUES (?, ?, ?, ?)",
+            params!["due and tagged", Cadence::days(1), Date::ymd(2022, 1, 1), 1],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params!["due", Cadence::days(1), Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES (?, ?, ?)",
+            params![
+                "not due",
+                Cadence::days(1),
+                Date::today() + Cadence::weeks(1)
+            ],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    fn server(token: Option<&str>) -> Server {
+        Server::bind("127.0.0.1:0".parse().unwrap(), token.map(str::to_string)).unwrap()
+    }
+
+    fn request(method: Method, url: &str, token: Option<&str>, body: &str) -> Request {
+        Request {
+            method,
+            url: url.to_string(),
+            token: token.map(str::to_string),
+            body: body.to_string(),
+        }
+    }
+
+    fn get(url: &str) -> Response {
+        server(None).respond(&mut conn(), &request(Method::Get, url, None, ""))
+    }
+
+    fn json(response: &Response) -> serde_json::Value {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    fn texts(response: &Response) -> Vec<String> {
+        let items: Vec<serde_json::Value> = serde_json::from_str(&response.body).unwrap();
+
+        items
+            .iter()
+            .map(|item| item["text"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    mod route {
+        use super::*;
+
+        #[test]
+        fn ready() {
+            let response = get("/ready.json");
+
+            assert_eq!(200, response.status);
+            assert_eq!("application/json", response.content_type);
+            assert_eq!(vec!["due and tagged", "due"], texts(&response));
+        }
+
+        #[test]
+        fn ready_with_tag() {
+            assert_eq!(
+                vec!["due and tagged"],
+                texts(&get("/ready.json?tag=my%20tag"))
+            );
+        }
+
+        #[test]
+        fn ready_with_limit() {
+            assert_eq!(1, texts(&get("/ready.json?limit=1")).len());
+        }
+
+        #[test]
+        fn items() {
+            assert_eq!(3, texts(&get("/items.json")).len());
+        }
+
+        #[test]
+        fn items_match_all_json_output() {
+            let expected = serde_json::to_value(all::query(&conn(), None, false).unwrap()).unwrap();
+
+            let response = json(&get("/items.json"));
+            assert_eq!(expected, response);
+            assert_eq!("learning", response[0]["status"]);
+        }
+
+        #[test]
+        fn items_with_tag() {
+            assert_eq!(
+                vec!["due and tagged"],
+                texts(&get("/items.json?tag=my+tag"))
+            );
+        }
+
+        #[test]
+        fn calendar() {
+            let response = get("/calendar.ics?horizon=2d");
+
+            assert_eq!(200, response.status);
+            assert_eq!("text/calendar; charset=utf-8", response.content_type);
+            assert!(response.body.contains("SUMMARY:not due"));
+        }
+
+        #[test]
+        fn bad_parameter() {
+            let response = get("/ready.json?limit=lots");
+
+            assert_eq!(400, response.status);
+            assert!(response.body.contains("limit"));
+        }
+
+        #[test]
+        fn not_found() {
+            assert_eq!(404, get("/nope").status);
+        }
+
+        #[test]
+        fn unknown_tag() {
+            for url in [
+                "/items.json?tag=nope",
+                "/ready.json?tag=nope",
+                "/calendar.ics?tag=nope",
+            ] {
+                let response = get(url);
+
+                assert_eq!(404, response.status);
+                assert_eq!(
+                    "couldn't find a tag named \"nope\"",
+                    json(&response)["error"]
+                );
+            }
+        }
+
+        #[test]
+        fn method_not_allowed() {
+            assert_eq!(
+                405,
+                server(None)
+                    .respond(&mut conn(), &request(Method::Post, "/ready.json", None, ""))
+                    .status
+            );
+        }
+    }
+
+    mod mutations {
+        use super::*;
+
+        fn send(conn: &mut Connection, method: Method, url: &str, body: &str) -> Response {
+            server(Some("secret")).respond(conn, &request(method, url, Some("secret"), body))
+        }
+
+        #[test]
+        fn read_only_without_a_token() {
+            let response = server(None).respond(
+                &mut conn(),
+                &request(Method::Post, "/items", Some("secret"), r#"{"text":"new"}"#),
+            );
+
+            assert_eq!(403, response.status);
+        }
+
+        #[test]
+        fn wrong_token() {
+            let response = server(Some("secret")).respond(
+                &mut conn(),
+                &request(Method::Post, "/items", Some("guess"), r#"{"text":"new"}"#),
+            );
+
+            assert_eq!(401, response.status);
+        }
+
+        #[test]
+        fn missing_token() {
+            let response = server(Some("secret")).respond(
+                &mut conn(),
+                &request(Method::Post, "/items", None, r#"{"text":"new"}"#),
+            );
+
+            assert_eq!(401, response.status);
+        }
+
+        #[test]
+        fn add() {
+            let mut conn = conn();
+            let response = send(
+                &mut conn,
+                Method::Post,
+                "/items",
+                r#"{"text":"new","tag":"fresh","cadence":"2w"}"#,
+            );
+
+            assert_eq!(201, response.status);
+            let id = json(&response)["id"].as_u64().unwrap();
+            let item = Item::get(id, &conn).unwrap();
+            assert_eq!(
+                serde_json::to_value(&item).unwrap(),
+                json(&response),
+                "should be the same shape as --format json"
+            );
+            assert_eq!(Cadence::weeks(2), item.cadence);
+            assert!(item.tag_id.is_some());
+        }
+
+        #[test]
+        fn add_duplicate() {
+            let response = send(&mut conn(), Method::Post, "/items", r#"{"text":"DUE"}"#);
+
+            assert_eq!(409, response.status);
+            assert_eq!(2, json(&response)["existing"]["id"]);
+        }
+
+        #[test]
+        fn add_bad_body() {
+            let response = send(&mut conn(), Method::Post, "/items", r#"{"txt":"new"}"#);
+
+            assert_eq!(400, response.status);
+        }
+
+        #[test]
+        fn edit() {
+            let mut conn = conn();
+            let response = send(
+                &mut conn,
+                Method::Patch,
+                "/items/2",
+                r#"{"text":"edited","cadence":"3d"}"#,
+            );
+
+            assert_eq!(200, response.status);
+            let item = Item::get(2, &conn).unwrap();
+            assert_eq!("edited", item.text);
+            assert_eq!(Cadence::days(3), item.cadence);
+        }
+
+        #[test]
+        fn edit_conflicting_changes() {
+            let response = send(
+                &mut conn(),
+                Method::Patch,
+                "/items/2",
+                r#"{"cadence":"3d","bump":"later"}"#,
+            );
+
+            assert_eq!(400, response.status);
+        }
+
+        #[test]
+        fn finish() {
+            let mut conn = conn();
+            let response = send(
+                &mut conn,
+                Method::Post,
+                "/items/2/finish",
+                r#"{"bump":"just-right"}"#,
+            );
+
+            assert_eq!(200, response.status);
+            assert!(Item::get(2, &conn).unwrap().next > Date::ymd(2022, 1, 1));
+        }
+
+        #[test]
+        fn finish_not_due_yet() {
+            let response = send(
+                &mut conn(),
+                Method::Post,
+                "/items/3/finish",
+                r#"{"bump":"just-right"}"#,
+            );
+
+            assert_eq!(409, response.status);
+            assert!(json(&response)["error"]
+                .as_str()
+                .unwrap()
+                .contains("before it's due"));
+        }
+
+        #[test]
+        fn finish_not_found() {
+            let response = send(
+                &mut conn(),
+                Method::Post,
+                "/items/99/finish",
+                r#"{"bump":"just-right"}"#,
+            );
+
+            assert_eq!(404, response.status);
+        }
+
+        #[test]
+        fn snooze() {
+            let mut conn = conn();
+            let response = send(
+                &mut conn,
+                Method::Post,
+                "/items/2/snooze",
+                r#"{"until":"2w"}"#,
+            );
+
+            assert_eq!(200, response.status);
+            assert_eq!(
+                Some(Date::today() + Cadence::weeks(2)),
+                Item::get(2, &conn).unwrap().paused_until
+            );
+        }
+
+        #[test]
+        fn delete() {
+            let mut conn = conn();
+            let response = send(&mut conn, Method::Delete, "/items/2", "");
+
+            assert_eq!(200, response.status);
+            assert_eq!("due", json(&response)["text"]);
+            assert!(Item::get(2, &conn).is_err());
+            assert_eq!(1, Trashed::all(&conn).unwrap().count());
+        }
+
+        #[test]
+        fn failures_roll_back() {
+            let mut conn = conn();
+            // the tag gets created before the bump fails to parse
+            let response = send(
+                &mut conn,
+                Method::Patch,
+                "/items/2",
+                r#"{"tag":"new tag","bump":"sideways"}"#,
+            );
+
+            assert_eq!(400, response.status);
+            assert!(Tag::get_by_name(&conn, "new tag").is_err());
+        }
+    }
+
+    mod decode {
+        use super::*;
+
+        #[test]
+        fn plain() {
+            assert_eq!("tag", decode("tag"));
+        }
+
+        #[test]
+        fn escapes() {
+            assert_eq!("my tag/ü", decode("my%20tag%2F%C3%BC"));
+        }
+
+        #[test]
+        fn plus_is_space() {
+            assert_eq!("my tag", decode("my+tag"));
+        }
+
+        #[test]
+        fn invalid_escapes_are_left_alone() {
+            assert_eq!("100%", decode("100%"));
+            assert_eq!("%zz", decode("%zz"));
+        }
+    }
+
+    mod http {
+        use super::*;
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::sync::Arc;
+
+        fn send(addr: SocketAddr, line: &str, token: Option<&str>, body: &str) -> (String, String) {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            let authorization = token
+                .map(|token| format!("Authorization: Bearer {token}\r\n"))
+                .unwrap_or_default();
+            let request = format!(
+                "{line} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{authorization}Content-Length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(request.as_bytes()).unwrap();
+
+            let mut raw = String::new();
+            stream.read_to_string(&mut raw).unwrap();
+
+            let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+            (head.lines().next().unwrap().to_string(), body.to_string())
+        }
+
+        #[test]
+        fn serves_on_an_ephemeral_port() {
+            let server = Arc::new(server(Some("secret")));
+            let addr = server.address().unwrap();
+
+            let mut conn = conn();
+            let running = Arc::clone(&server);
+            let handle = std::thread::spawn(move || running.run(&mut conn));
+
+            let (status, body) = send(addr, "GET /ready.json?tag=my%20tag", None, "");
+            assert_eq!("HTTP/1.1 200 OK", status);
+            assert!(body.contains("due and tagged"));
+
+            let (status, body) = send(addr, "GET /calendar.ics", None, "");
+            assert_eq!("HTTP/1.1 200 OK", status);
+            assert!(body.starts_with("BEGIN:VCALENDAR\r\n"));
+
+            let (status, _) = send(addr, "GET /nope", None, "");
+            assert_eq!("HTTP/1.1 404 Not Found", status);
+
+            let (status, _) = send(addr, "POST /items/2/finish", None, r#"{"bump":"later"}"#);
+            assert_eq!("HTTP/1.1 401 Unauthorized", status);
+
+            let (status, body) = send(
+                addr,
+                "POST /items/2/finish",
+                Some("secret"),
+                r#"{"bump":"later"}"#,
+            );
+            assert_eq!("HTTP/1.1 200 OK", status);
+            assert!(body.contains("\"id\":2"));
+
+            server.stop();
+            handle.join().unwrap();
+        }
+    }
+}
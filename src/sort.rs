@@ -0,0 +1,201 @@
+use crate::date::Date;
+use crate::item::Item;
+use crate::vacation::{self, Vacation};
+use std::collections::VecDeque;
+
+/// Ways to order the items that are ready. All of these expect items to
+/// come in `next` order (as `Item::due` gives them) and keep that order
+/// for ties.
+#[derive(clap::ArgEnum, Clone, Debug, PartialEq)]
+pub enum Sort {
+    /// Items that were due earliest first
+    Next,
+
+    /// Items that are most overdue compared to their cadence first, so a
+    /// daily item that's a day late comes before a yearly item that's a
+    /// week late. Days spent on vacation don't count as overdue.
+    OverdueRatio,
+
+    /// Take one item from each tag in turn
+    RoundRobin,
+
+    /// Items with the shortest cadence first
+    ShortestCadence,
+
+    /// Shuffle the items. The same seed always gives the same order.
+    Random,
+}
+
+impl Sort {
+    pub fn apply(
+        &self,
+        mut items: Vec<Item>,
+        today: Date,
+        vacations: &[Vacation],
+        seed: u64,
+    ) -> Vec<Item> {
+        match self {
+            Sort::Next => items,
+            Sort::OverdueRatio => {
+                items.sort_by(|a, b| {
+                    overdue_ratio(b, today, vacations)
+                        .total_cmp(&overdue_ratio(a, today, vacations))
+                });
+                items
+            }
+            Sort::RoundRobin => round_robin(items),
+            Sort::ShortestCadence => {
+                items.sort_by_key(|item| item.cadence.days);
+                items
+            }
+            Sort::Random => {
+                shuffle(&mut items, seed);
+                items
+            }
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn overdue_ratio(item: &Item, today: Date, vacations: &[Vacation]) -> f64 {
+    vacation::days_late(vacations, item, today) as f64 / item.cadence.days.max(1) as f64
+}
+
+fn round_robin(items: Vec<Item>) -> Vec<Item> {
+    // tags go in the order of their earliest item, and items without a tag
+    // get a turn like any tag would
+    let mut tags: Vec<(Option<u64>, VecDeque<Item>)> = Vec::new();
+    for item in items {
+        match tags.iter_mut().find(|(tag_id, _)| *tag_id == item.tag_id) {
+            Some((_, queue)) => queue.push_back(item),
+            None => tags.push((item.tag_id, VecDeque::from([item]))),
+        }
+    }
+
+    let mut out = Vec::new();
+    while !tags.is_empty() {
+        for (_, queue) in &mut tags {
+            out.extend(queue.pop_front());
+        }
+        tags.retain(|(_, queue)| !queue.is_empty());
+    }
+
+    out
+}
+
+/// A Fisher-Yates shuffle driven by splitmix64. We don't need anything
+/// stronger than this to mix up a review session, and it means we don't
+/// have to pull in a dependency for it.
+fn shuffle(items: &mut [Item], seed: u64) {
+    let mut state = seed;
+
+    for i in (1..items.len()).rev() {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        // the remainder is at most i, so it always fits back into a usize
+        #[allow(clippy::cast_possible_truncation)]
+        let j = (z % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cadence::Cadence;
+    use crate::pid::Pid;
+
+    fn item(id: u64, tag_id: Option<u64>, cadence: Cadence, days_late: i64) -> Item {
+        Item {
+            id,
+            text: format!("item {id}"),
+            tag_id,
+            cadence,
+            next: Date::ymd(2022, 1, 31) - Cadence::days(days_late),
+            pid: Pid::default(),
+            archived: false,
+            paused_until: None,
+            learning_steps: 0,
+        }
+    }
+
+    fn ids(items: &[Item]) -> Vec<u64> {
+        items.iter().map(|item| item.id).collect()
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            item(1, Some(1), Cadence::years(1), 7),
+            item(2, Some(1), Cadence::weeks(1), 3),
+            item(3, None, Cadence::days(1), 1),
+            item(4, Some(2), Cadence::days(1), 0),
+        ]
+    }
+
+    #[test]
+    fn next_keeps_order() {
+        assert_eq!(
+            vec![1, 2, 3, 4],
+            ids(&Sort::Next.apply(items(), Date::ymd(2022, 1, 31), &[], 0))
+        );
+    }
+
+    #[test]
+    fn overdue_ratio_is_relative_to_cadence() {
+        assert_eq!(
+            vec![3, 2, 1, 4],
+            ids(&Sort::OverdueRatio.apply(items(), Date::ymd(2022, 1, 31), &[], 0))
+        );
+    }
+
+    #[test]
+    fn overdue_ratio_leaves_out_vacations() {
+        let vacations = vec![Vacation {
+            id: 1,
+            tag_id: Some(1),
+            starts: Date::ymd(2022, 1, 20),
+            ends: Date::ymd(2022, 1, 31),
+        }];
+
+        assert_eq!(
+            vec![3, 1, 2, 4],
+            ids(&Sort::OverdueRatio.apply(items(), Date::ymd(2022, 1, 31), &vacations, 0))
+        );
+    }
+
+    #[test]
+    fn round_robin_alternates_tags() {
+        assert_eq!(
+            vec![1, 3, 4, 2],
+            ids(&Sort::RoundRobin.apply(items(), Date::ymd(2022, 1, 31), &[], 0))
+        );
+    }
+
+    #[test]
+    fn shortest_cadence_keeps_next_order_for_ties() {
+        assert_eq!(
+            vec![3, 4, 2, 1],
+            ids(&Sort::ShortestCadence.apply(items(), Date::ymd(2022, 1, 31), &[], 0))
+        );
+    }
+
+    #[test]
+    fn random_depends_on_seed() {
+        let today = Date::ymd(2022, 1, 31);
+        let many: Vec<Item> = (0..20)
+            .map(|id| item(id, None, Cadence::days(1), 0))
+            .collect();
+
+        let first = ids(&Sort::Random.apply(many.clone(), today, &[], 1));
+        assert_eq!(first, ids(&Sort::Random.apply(many.clone(), today, &[], 1)));
+        assert_ne!(first, ids(&Sort::Random.apply(many.clone(), today, &[], 2)));
+
+        let mut sorted = first;
+        sorted.sort_unstable();
+        assert_eq!(ids(&many), sorted);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::cadence::Cadence;
+use crate::history::Finish;
+use crate::item::{Bump, Item};
+use anyhow::Result;
+use core::fmt::{self, Display, Formatter};
+use rusqlite::Connection;
+
+/// How many finishes in a row have to be `just-right` before we call an
+/// item settled.
+static SETTLED_STREAK: usize = 3;
+
+/// How big the PID integral (in days) can be on a settled item. Each
+/// `just-right` halves it, so this only matters for items that got a lot of
+/// feedback in one direction before their streak started.
+static SETTLED_INTEGRAL: f64 = 1.0;
+
+/// Whether an item has found its rhythm yet.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// We're still adjusting the cadence based on feedback
+    Learning,
+
+    /// The cadence has been right for a while, so it's unlikely to change
+    /// much from here
+    Settled,
+}
+
+impl Status {
+    pub fn of(item: &Item, conn: &Connection) -> Result<Status> {
+        let recent: Vec<Bump> = Finish::recent(conn, item.id, SETTLED_STREAK)?
+            .map(|finish| finish.bump)
+            .collect();
+
+        Ok(Status::from_feedback(item, &recent))
+    }
+
+    /// Decide based on the item's PID state and its most recent feedback
+    /// (most recent first.)
+    fn from_feedback(item: &Item, recent: &[Bump]) -> Status {
+        let streak =
+            recent.len() >= SETTLED_STREAK && recent.iter().all(|bump| *bump == Bump::JustRight);
+
+        if item.learning_steps == 0
+            && streak
+            && item.pid.last_error.abs() < f64::EPSILON
+            && item.pid.integral.abs() <= SETTLED_INTEGRAL
+        {
+            Status::Settled
+        } else {
+            Status::Learning
+        }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, out: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Learning => write!(out, "learning"),
+            Status::Settled => write!(out, "settled"),
+        }
+    }
+}
+
+/// A starting cadence for new items in a tag, based on the items in it
+/// that have already settled.
+#[derive(Debug, PartialEq)]
+pub struct Suggestion {
+    pub cadence: Cadence,
+
+    /// How many settled items the suggestion is based on
+    pub settled: usize,
+}
+
+impl Suggestion {
+    pub fn for_tag(tag_id: u64, conn: &Connection) -> Result<Option<Suggestion>> {
+        let mut settled = Vec::new();
+        for item in Item::all(conn)?.filter(|item| item.tag_id == Some(tag_id)) {
+            if Status::of(&item, conn)? == Status::Settled {
+                settled.push(item.cadence);
+            }
+        }
+
+        Ok(
+            Cadence::median(settled.iter().copied()).map(|cadence| Suggestion {
+                cadence,
+                settled: settled.len(),
+            }),
+        )
+    }
+}
+
+/// An item along with its status, for JSON output.
+#[derive(Debug, serde::Serialize)]
+pub struct WithStatus {
+    #[serde(flatten)]
+    pub item: Item,
+    pub status: Status,
+}
+
+impl WithStatus {
+    pub fn new(item: Item, conn: &Connection) -> Result<WithStatus> {
+        let status = Status::of(&item, conn)?;
+
+        Ok(WithStatus { item, status })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::Date;
+    use crate::pid::Pid;
+    use rusqlite::params;
+
+    fn item(integral: f64, last_error: f64) -> Item {
+        Item {
+            id: 1,
+            text: "test".into(),
+            tag_id: None,
+            cadence: Cadence::days(7),
+            next: Date::ymd(2022, 1, 1),
+            pid: Pid {
+                integral,
+                last_error,
+            },
+            archived: false,
+            paused_until: None,
+            learning_steps: 0,
+        }
+    }
+
+    #[test]
+    fn new_items_are_learning() {
+        assert_eq!(
+            Status::Learning,
+            Status::from_feedback(&item(0.0, 0.0), &[])
+        );
+    }
+
+    #[test]
+    fn streak_of_just_right_is_settled() {
+        assert_eq!(
+            Status::Settled,
+            Status::from_feedback(&item(0.5, 0.0), &vec![Bump::JustRight; 3])
+        );
+    }
+
+    #[test]
+    fn short_streak_is_learning() {
+        assert_eq!(
+            Status::Learning,
+            Status::from_feedback(
+                &item(0.5, 0.0),
+                &[Bump::JustRight, Bump::JustRight, Bump::Later]
+            )
+        );
+    }
+
+    #[test]
+    fn learning_steps_are_learning() {
+        let mut item = item(0.0, 0.0);
+        item.learning_steps = 1;
+
+        assert_eq!(
+            Status::Learning,
+            Status::from_feedback(&item, &vec![Bump::JustRight; 3])
+        );
+    }
+
+    #[test]
+    fn large_integral_is_learning() {
+        assert_eq!(
+            Status::Learning,
+            Status::from_feedback(&item(2.0, 0.0), &vec![Bump::JustRight; 3])
+        );
+    }
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::runner().run(&mut conn).unwrap();
+
+        conn
+    }
+
+    fn settle(item: &Item, conn: &Connection) {
+        for _ in 0..3 {
+            Finish::record(conn, item, &Bump::JustRight, item.next).unwrap();
+        }
+    }
+
+    #[test]
+    fn reads_history() {
+        let conn = conn();
+        conn.execute(
+            "INSERT INTO items (text, cadence, next) VALUES ('test', 7, ?)",
+            [Date::ymd(2022, 1, 1)],
+        )
+        .unwrap();
+        let item = Item::get(1, &conn).unwrap();
+
+        settle(&item, &conn);
+
+        assert_eq!(Status::Settled, Status::of(&item, &conn).unwrap());
+    }
+
+    #[test]
+    fn suggests_median_of_settled_items() {
+        let conn = conn();
+        conn.execute("INSERT INTO tags (name) VALUES ('bookmarks')", [])
+            .unwrap();
+        for cadence in [40, 42, 50, 1] {
+            conn.execute(
+                "INSERT INTO items (text, cadence, next, tag_id) VALUES ('test', ?, ?, 1)",
+                params![cadence, Date::ymd(2022, 1, 1)],
+            )
+            .unwrap();
+        }
+        for id in 1..=3 {
+            settle(&Item::get(id, &conn).unwrap(), &conn);
+        }
+
+        assert_eq!(
+            Some(Suggestion {
+                cadence: Cadence::days(42),
+                settled: 3
+            }),
+            Suggestion::for_tag(1, &conn).unwrap()
+        );
+        assert_eq!(None, Suggestion::for_tag(2, &conn).unwrap());
+    }
+}
@@ -15,6 +15,15 @@ impl Tag {
         })
     }
 
+    pub fn get(conn: &Connection, id: u64) -> Result<Tag> {
+        conn.query_row(
+            "SELECT id, name FROM tags WHERE id = ?",
+            [id],
+            Self::from_row,
+        )
+        .with_context(|| format!("could not get the tag with ID {id}"))
+    }
+
     pub fn get_by_name(conn: &Connection, name: &str) -> Result<Tag> {
         conn.query_row(
             "SELECT id, name FROM tags WHERE name = ?",
@@ -0,0 +1,328 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::history::Finish;
+use crate::item::Item;
+use crate::pid::Pid;
+use crate::tag::Tag;
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, Row};
+
+#[derive(Debug, serde::Serialize, PartialEq)]
+pub struct Trashed {
+    pub id: u64,
+    pub item_id: u64,
+    pub text: String,
+    pub tag: Option<String>,
+
+    // scheduling
+    pub cadence: Cadence,
+    pub next: Date,
+
+    #[serde(flatten)]
+    pub pid: Pid,
+
+    // retirement
+    pub archived: bool,
+    pub paused_until: Option<Date>,
+
+    pub learning_steps: u32,
+
+    pub deleted_at: Date,
+}
+
+impl Trashed {
+    fn from_row(row: &'_ Row<'_>) -> rusqlite::Result<Trashed> {
+        Ok(Trashed {
+            id: row.get(0)?,
+            item_id: row.get(1)?,
+            text: row.get(2)?,
+            tag: row.get(3)?,
+            cadence: row.get(4)?,
+            next: row.get(5)?,
+            pid: Pid {
+                integral: row.get(6)?,
+                last_error: row.get(7)?,
+            },
+            archived: row.get(8)?,
+            paused_until: row.get(9)?,
+            deleted_at: row.get(10)?,
+            learning_steps: row.get(11)?,
+        })
+    }
+
+    /// Move an item out of the items table and into the trash. We store the
+    /// tag by name instead of by ID so that restoring an item still works
+    /// even if the tags table has been rebuilt in the meantime.
+    pub fn trash(item: &Item, conn: &Connection) -> Result<Trashed> {
+        let tag = match item.tag_id {
+            Some(tag_id) => Some(Tag::get(conn, tag_id)?.name),
+            None => None,
+        };
+
+        let id: u64 = conn
+            .query_row(
+                "INSERT INTO trash (item_id, text, tag, cadence, next, integral, last_error, archived, paused_until, deleted_at, learning_steps) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+                params![
+                    item.id,
+                    item.text,
+                    tag,
+                    item.cadence,
+                    item.next,
+                    item.pid.integral,
+                    item.pid.last_error,
+                    item.archived,
+                    item.paused_until,
+                    Date::today(),
+                    item.learning_steps,
+                ],
+                |row| row.get(0),
+            )
+            .with_context(|| format!("could not move item with ID {} to the trash", item.id))?;
+
+        Finish::trash(conn, item.id, id)?;
+
+        match conn.execute("DELETE FROM items WHERE id = ?", [item.id]) {
+            Ok(1) => (),
+            Ok(other) => bail!(
+                "Deleted {} rows for ID {}. Please report this as a bug!",
+                other,
+                item.id
+            ),
+            Err(err) => {
+                return Err(err).with_context(|| format!("could not drop item with ID {}", item.id))
+            }
+        }
+
+        Self::get(id, conn)
+    }
+
+    pub fn get(id: u64, conn: &Connection) -> Result<Trashed> {
+        conn.query_row(
+            "SELECT id, item_id, text, tag, cadence, next, integral, last_error, archived, paused_until, deleted_at, learning_steps FROM trash WHERE id = ?",
+            [id],
+            Self::from_row,
+        )
+        .with_context(|| format!("could not retrieve trashed item with ID {id}"))
+    }
+
+    /// Get the most recently trashed copy of an item. Item IDs can be reused
+    /// once they're deleted, so there may be more than one!
+    pub fn latest_for_item(item_id: u64, conn: &Connection) -> Result<Trashed> {
+        conn.query_row(
+            "SELECT id, item_id, text, tag, cadence, next, integral, last_error, archived, paused_until, deleted_at, learning_steps FROM trash WHERE item_id = ? ORDER BY id DESC LIMIT 1",
+            [item_id],
+            Self::from_row,
+        )
+        .with_context(|| format!("could not find item with ID {item_id} in the trash"))
+    }
+
+    pub fn all(conn: &Connection) -> Result<impl Iterator<Item = Trashed>> {
+        let mut statement = conn
+            .prepare("SELECT id, item_id, text, tag, cadence, next, integral, last_error, archived, paused_until, deleted_at, learning_steps FROM trash ORDER BY id ASC")
+            .context("could not prepare query to get trashed items")?;
+
+        let trashed = statement
+            .query_map([], Self::from_row)?
+            .collect::<rusqlite::Result<Vec<Trashed>>>()
+            .context("could not pull rows")?;
+
+        Ok(trashed.into_iter())
+    }
+
+    /// Put this item back in the items table, keeping its original ID if
+    /// nothing else has taken it in the meantime.
+    pub fn restore(&self, conn: &Connection) -> Result<Item> {
+        let tag_id: Option<u64> = match &self.tag {
+            Some(tag_name) => Some(Tag::get_or_create_by_name(conn, tag_name)?.id),
+            None => None,
+        };
+
+        let id_taken: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM items WHERE id = ?)",
+                [self.item_id],
+                |row| row.get(0),
+            )
+            .context("could not check if the original ID is still free")?;
+
+        let id: u64 = conn
+            .query_row(
+                "INSERT INTO items (id, text, tag_id, cadence, next, integral, last_error, archived, paused_until, learning_steps) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+                params![
+                    if id_taken { None } else { Some(self.item_id) },
+                    self.text,
+                    tag_id,
+                    self.cadence,
+                    self.next,
+                    self.pid.integral,
+                    self.pid.last_error,
+                    self.archived,
+                    self.paused_until,
+                    self.learning_steps,
+                ],
+                |row| row.get(0),
+            )
+            .with_context(|| format!("could not restore item with ID {}", self.item_id))?;
+
+        Finish::restore(conn, self.id, id)?;
+
+        conn.execute("DELETE FROM trash WHERE id = ?", [self.id])
+            .context("could not remove the restored item from the trash")?;
+
+        Item::get(id, conn)
+    }
+
+    /// Permanently delete trashed items. If `before` is present, only items
+    /// deleted before that date are removed.
+    pub fn empty(before: Option<Date>, conn: &Connection) -> Result<usize> {
+        let emptied = match before {
+            Some(date) => conn.execute("DELETE FROM trash WHERE deleted_at < ?", [date]),
+            None => conn.execute("DELETE FROM trash", []),
+        }
+        .context("could not empty the trash")?;
+
+        Finish::forget_emptied(conn)?;
+
+        Ok(emptied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::Bump;
+
+    fn conn() -> Connection {
+        let mut conn = Connection::open_in_memory().expect("couldn't open an in-memory database");
+        crate::db::migrations::runner()
+            .run(&mut conn)
+            .expect("couldn't migrate database");
+
+        conn.execute("INSERT INTO tags (id, name) VALUES (1, \"tag\")", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO items (text, cadence, next, tag_id, integral, last_error) VALUES (?, ?, ?, ?, ?, ?)",
+            params!["test", Cadence::weeks(1), Date::ymd(2022, 1, 1), 1, 1.5, -0.5],
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn trash_removes_item() {
+        let conn = conn();
+        let item = Item::get(1, &conn).unwrap();
+
+        Trashed::trash(&item, &conn).unwrap();
+
+        assert!(Item::get(1, &conn).is_err());
+    }
+
+    #[test]
+    fn trash_keeps_tag_and_pid() {
+        let conn = conn();
+        let item = Item::get(1, &conn).unwrap();
+
+        let trashed = Trashed::trash(&item, &conn).unwrap();
+
+        assert_eq!(Some("tag".to_string()), trashed.tag);
+        assert_eq!(item.pid, trashed.pid);
+        assert_eq!(item.cadence, trashed.cadence);
+    }
+
+    #[test]
+    fn restore_roundtrips() {
+        let conn = conn();
+        let item = Item::get(1, &conn).unwrap();
+
+        let restored = Trashed::trash(&item, &conn)
+            .unwrap()
+            .restore(&conn)
+            .unwrap();
+
+        assert_eq!(item, restored);
+        assert_eq!(0, Trashed::all(&conn).unwrap().count());
+    }
+
+    #[test]
+    fn new_item_with_reused_id_starts_without_history() {
+        let conn = conn();
+        let item = Item::get(1, &conn).unwrap();
+        for _ in 0..3 {
+            Finish::record(&conn, &item, &Bump::MuchLater, item.next).unwrap();
+        }
+        let trashed = Trashed::trash(&item, &conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO items (text, next) VALUES (\"brand new\", ?)",
+            [Date::today()],
+        )
+        .unwrap();
+        assert_eq!("brand new", Item::get(1, &conn).unwrap().text);
+        assert_eq!(0, Finish::recent(&conn, 1, 3).unwrap().count());
+
+        let restored = trashed.restore(&conn).unwrap();
+        assert_eq!(3, Finish::recent(&conn, restored.id, 3).unwrap().count());
+        assert_eq!(0, Finish::recent(&conn, 1, 3).unwrap().count());
+    }
+
+    #[test]
+    fn emptying_forgets_history() {
+        let conn = conn();
+        let item = Item::get(1, &conn).unwrap();
+        Finish::record(&conn, &item, &Bump::Later, item.next).unwrap();
+        let trashed = Trashed::trash(&item, &conn).unwrap();
+
+        Trashed::empty(None, &conn).unwrap();
+
+        assert_eq!(0, Finish::trashed(&conn, trashed.id).unwrap().count());
+    }
+
+    #[test]
+    fn restore_uses_new_id_if_original_is_taken() {
+        let conn = conn();
+        let item = Item::get(1, &conn).unwrap();
+        let trashed = Trashed::trash(&item, &conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO items (id, text, next) VALUES (1, \"other\", ?)",
+            [Date::today()],
+        )
+        .unwrap();
+
+        let restored = trashed.restore(&conn).unwrap();
+
+        assert_ne!(1, restored.id);
+        assert_eq!(item.text, restored.text);
+    }
+
+    #[test]
+    fn latest_for_item_picks_most_recent() {
+        let conn = conn();
+        let first = Trashed::trash(&Item::get(1, &conn).unwrap(), &conn).unwrap();
+
+        conn.execute(
+            "INSERT INTO items (id, text, next) VALUES (1, \"other\", ?)",
+            [Date::today()],
+        )
+        .unwrap();
+        let second = Trashed::trash(&Item::get(1, &conn).unwrap(), &conn).unwrap();
+
+        let latest = Trashed::latest_for_item(1, &conn).unwrap();
+        assert_ne!(first.id, latest.id);
+        assert_eq!(second, latest);
+    }
+
+    #[test]
+    fn empty_respects_cutoff() {
+        let conn = conn();
+        Trashed::trash(&Item::get(1, &conn).unwrap(), &conn).unwrap();
+
+        assert_eq!(0, Trashed::empty(Some(Date::today()), &conn).unwrap());
+        assert_eq!(
+            1,
+            Trashed::empty(Some(Date::today() + Cadence::days(1)), &conn).unwrap()
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use crate::cadence::Cadence;
+use crate::date::Date;
+use crate::item::Item;
+use crate::reschedule;
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, Row};
+
+/// A stretch of days (inclusive on both ends) when nothing was getting
+/// done, either for every item or just the items in one tag.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Vacation {
+    pub id: u64,
+    pub tag_id: Option<u64>,
+    pub starts: Date,
+    pub ends: Date,
+}
+
+impl Vacation {
+    fn from_row(row: &'_ Row<'_>) -> rusqlite::Result<Vacation> {
+        Ok(Vacation {
+            id: row.get(0)?,
+            tag_id: row.get(1)?,
+            starts: row.get(2)?,
+            ends: row.get(3)?,
+        })
+    }
+
+    pub fn record(
+        conn: &Connection,
+        tag_id: Option<u64>,
+        starts: Date,
+        ends: Date,
+    ) -> Result<Vacation> {
+        conn.query_row(
+            "INSERT INTO vacations (tag_id, starts, ends) VALUES (?, ?, ?) RETURNING id, tag_id, starts, ends",
+            params![tag_id, starts, ends],
+            Self::from_row,
+        )
+        .context("could not record the vacation")
+    }
+
+    pub fn all(conn: &Connection) -> Result<impl Iterator<Item = Vacation>> {
+        let mut statement = conn
+            .prepare("SELECT id, tag_id, starts, ends FROM vacations ORDER BY starts ASC")
+            .context("could not prepare query to get vacations")?;
+
+        let vacations = statement
+            .query_map([], Self::from_row)?
+            .collect::<rusqlite::Result<Vec<Vacation>>>()
+            .context("could not pull rows")?;
+
+        Ok(vacations.into_iter())
+    }
+
+    fn applies_to(&self, item: &Item) -> bool {
+        self.tag_id.is_none() || self.tag_id == item.tag_id
+    }
+}
+
+/// How many days after `from` and up to `to` (inclusive) the item's owner
+/// was away. Overlapping vacations only count each day once.
+pub fn days_off(vacations: &[Vacation], item: &Item, from: Date, to: Date) -> i64 {
+    let mut days = 0;
+    let mut day = from + Cadence::days(1);
+
+    while day <= to {
+        if vacations.iter().any(|vacation| {
+            vacation.applies_to(item) && vacation.starts <= day && day <= vacation.ends
+        }) {
+            days += 1;
+        }
+        day = day + Cadence::days(1);
+    }
+
+    days
+}
+
+/// How many days late an item is as of `today`, not counting days spent on
+/// vacation (finishing late because you were away isn't really late.)
+pub fn days_late(vacations: &[Vacation], item: &Item, today: Date) -> i64 {
+    (today - item.next).num_days() - days_off(vacations, item, item.next, today)
+}
+
+/// Figure out where to move items that come due during a vacation: spread
+/// out over `spread` starting the day after it ends, earliest first. Items
+/// that are already overdue only move if the vacation has already started.
+/// Returns each moved item with its new `next`.
+pub fn reschedule(
+    mut items: Vec<Item>,
+    starts: Date,
+    ends: Date,
+    today: Date,
+    spread: Cadence,
+) -> Vec<Item> {
+    items.retain(|item| {
+        !item.archived
+            && (starts <= item.next || starts <= today)
+            && item.next <= ends
+            && item.paused_until.is_none_or(|until| until <= ends)
+    });
+
+    reschedule::spread(&mut items, ends + Cadence::days(1), spread);
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pid::Pid;
+
+    fn item(id: u64, tag_id: Option<u64>, next: Date) -> Item {
+        Item {
+            id,
+            text: format!("item {id}"),
+            tag_id,
+            cadence: Cadence::days(7),
+            next,
+            pid: Pid::default(),
+            archived: false,
+            paused_until: None,
+            learning_steps: 0,
+        }
+    }
+
+    fn vacation(tag_id: Option<u64>, starts: Date, ends: Date) -> Vacation {
+        Vacation {
+            id: 1,
+            tag_id,
+            starts,
+            ends,
+        }
+    }
+
+    #[test]
+    fn counts_days_off() {
+        let vacations = vec![
+            vacation(None, Date::ymd(2022, 1, 5), Date::ymd(2022, 1, 8)),
+            vacation(None, Date::ymd(2022, 1, 7), Date::ymd(2022, 1, 9)),
+            vacation(Some(2), Date::ymd(2022, 1, 12), Date::ymd(2022, 1, 20)),
+        ];
+
+        let untagged = item(1, None, Date::ymd(2022, 1, 1));
+        assert_eq!(
+            5,
+            days_off(
+                &vacations,
+                &untagged,
+                Date::ymd(2022, 1, 1),
+                Date::ymd(2022, 1, 15)
+            )
+        );
+
+        let tagged = item(1, Some(2), Date::ymd(2022, 1, 1));
+        assert_eq!(
+            9,
+            days_off(
+                &vacations,
+                &tagged,
+                Date::ymd(2022, 1, 1),
+                Date::ymd(2022, 1, 15)
+            )
+        );
+    }
+
+    #[test]
+    fn spreads_items_after_vacation() {
+        let ends = Date::ymd(2022, 1, 14);
+        let items = vec![
+            item(1, None, Date::ymd(2022, 1, 10)),
+            item(2, None, Date::ymd(2022, 1, 3)),
+            item(3, None, Date::ymd(2022, 1, 20)),
+            item(4, None, Date::ymd(2022, 1, 12)),
+            item(5, None, Date::ymd(2022, 1, 5)),
+        ];
+
+        let moved: Vec<(u64, Date)> = reschedule(
+            items,
+            Date::ymd(2022, 1, 1),
+            ends,
+            Date::ymd(2022, 1, 1),
+            Cadence::days(2),
+        )
+        .iter()
+        .map(|item| (item.id, item.next))
+        .collect();
+
+        assert_eq!(
+            vec![
+                (2, Date::ymd(2022, 1, 15)),
+                (5, Date::ymd(2022, 1, 15)),
+                (1, Date::ymd(2022, 1, 16)),
+                (4, Date::ymd(2022, 1, 16)),
+            ],
+            moved
+        );
+    }
+
+    #[test]
+    fn leaves_archived_and_paused_items() {
+        let ends = Date::ymd(2022, 1, 14);
+        let mut archived = item(1, None, Date::ymd(2022, 1, 10));
+        archived.archived = true;
+        let mut paused = item(2, None, Date::ymd(2022, 1, 10));
+        paused.paused_until = Some(Date::ymd(2022, 2, 1));
+
+        assert!(reschedule(
+            vec![archived, paused],
+            Date::ymd(2022, 1, 1),
+            ends,
+            Date::ymd(2022, 1, 1),
+            Cadence::days(3)
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn leaves_items_due_before_a_future_vacation() {
+        let today = Date::ymd(2022, 1, 1);
+        let items = vec![
+            item(1, None, Date::ymd(2021, 12, 30)),
+            item(2, None, Date::ymd(2022, 1, 2)),
+            item(3, None, Date::ymd(2022, 1, 12)),
+        ];
+
+        let moved: Vec<u64> = reschedule(
+            items.clone(),
+            Date::ymd(2022, 1, 10),
+            Date::ymd(2022, 1, 14),
+            today,
+            Cadence::days(1),
+        )
+        .iter()
+        .map(|item| item.id)
+        .collect();
+        assert_eq!(vec![3], moved);
+
+        // but once the vacation has started, anything overdue waits too
+        let moved: Vec<u64> = reschedule(
+            items,
+            Date::ymd(2022, 1, 1),
+            Date::ymd(2022, 1, 14),
+            today,
+            Cadence::days(1),
+        )
+        .iter()
+        .map(|item| item.id)
+        .collect();
+        assert_eq!(vec![1, 2, 3], moved);
+    }
+
+    #[test]
+    fn records_vacations() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::runner().run(&mut conn).unwrap();
+
+        let recorded =
+            Vacation::record(&conn, None, Date::ymd(2022, 1, 1), Date::ymd(2022, 1, 14)).unwrap();
+
+        assert_eq!(
+            vec![recorded],
+            Vacation::all(&conn).unwrap().collect::<Vec<_>>()
+        );
+    }
+}